@@ -0,0 +1,3 @@
+fn main() -> anyhow::Result<()> {
+    aoc_2019::day19::main()
+}
@@ -1,3 +1,4 @@
+use crate::Solution;
 use itertools::Itertools;
 use std::{collections::HashMap, env::args, error::Error, fs, str};
 
@@ -9,24 +10,42 @@ use nom::{
     IResult,
 };
 
-fn main() -> Result<(), Box<dyn Error>> {
+/// Entry point of the `day14` binary.
+pub fn main() -> Result<(), Box<dyn Error>> {
     let path = args()
         .nth(1)
         .ok_or("path to input file not provided".to_owned())?;
     let input = fs::read_to_string(path)?;
-    let program = parse(&input)?;
+    let program = Day14::parse(&input)?;
 
-    let mut sys = SystemV1::new();
-    let ans1 = sys.run(&program);
+    let ans1 = Day14::part1(&program)?;
     println!("Part1: {ans1}");
 
-    let mut sys = SystemV2::new();
-    let ans2 = sys.run(&program);
-    println!("Part1: {ans2}");
+    let ans2 = Day14::part2(&program)?;
+    println!("Part2: {ans2}");
 
     Ok(())
 }
 
+pub struct Day14;
+
+impl Solution for Day14 {
+    type Input = Vec<Instruction>;
+    type Output = u64;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        parse(input).map_err(anyhow::Error::msg)
+    }
+
+    fn part1(program: &Self::Input) -> anyhow::Result<u64> {
+        Ok(SystemV1::new().run(program))
+    }
+
+    fn part2(program: &Self::Input) -> anyhow::Result<u64> {
+        Ok(SystemV2::new().run(program))
+    }
+}
+
 #[derive(Default)]
 struct SystemV1 {
     mem: HashMap<u64, u64>,
@@ -45,20 +64,20 @@ impl<'a> SystemV2<'a> {
         Self::default()
     }
 
-    fn step(&mut self, instr: &Instruction<'a>) {
-        match *instr {
+    fn step(&mut self, instr: &'a Instruction) {
+        match instr {
             Instruction::Mask(mask) => {
                 self.mask = mask;
             }
             Instruction::Mem(address, value) => {
-                for addr in generate_addresses(self.mask, address) {
-                    self.mem.insert(addr, value);
+                for addr in generate_addresses(self.mask, *address) {
+                    self.mem.insert(addr, *value);
                 }
             }
         }
     }
 
-    fn run(&mut self, program: &[Instruction<'a>]) -> u64 {
+    fn run(&mut self, program: &'a [Instruction]) -> u64 {
         for instruction in program {
             self.step(instruction);
         }
@@ -73,13 +92,13 @@ impl SystemV1 {
     }
 
     fn step(&mut self, instr: &Instruction) {
-        match *instr {
+        match instr {
             Instruction::Mask(mask) => {
                 self.mask1 = u64::from_str_radix(&mask.replace('X', "1"), 2).unwrap();
                 self.mask2 = u64::from_str_radix(&mask.replace('X', "0"), 2).unwrap();
             }
             Instruction::Mem(address, value) => {
-                self.mem.insert(address, (value & self.mask1) | self.mask2);
+                self.mem.insert(*address, (value & self.mask1) | self.mask2);
             }
         }
     }
@@ -129,8 +148,8 @@ fn generate_addresses(mask: &str, address: u64) -> impl Iterator<Item = u64> + '
 }
 
 #[derive(PartialEq, Debug)]
-enum Instruction<'a> {
-    Mask(&'a str),
+pub enum Instruction {
+    Mask(String),
     Mem(u64, u64),
 }
 
@@ -165,7 +184,7 @@ fn mem() {
 fn parse_mask(input: &str) -> IResult<&str, Instruction> {
     let (input, _) = tag("mask = ")(input)?;
     let (input, output) = alphanumeric1(input)?;
-    Ok((input, Instruction::Mask(output)))
+    Ok((input, Instruction::Mask(output.to_owned())))
 }
 
 #[test]
@@ -175,7 +194,7 @@ fn mask() {
         parse_mask(input),
         Ok((
             "",
-            Instruction::Mask("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X")
+            Instruction::Mask("XXXXXXXXXXXXXXXXXXXXXXXXXXXXX1XXXX0X".to_owned())
         ))
     );
 }
@@ -0,0 +1,161 @@
+use std::{
+    collections::HashSet,
+    fmt::{self, Write},
+};
+
+use super::Game;
+
+/// Statistics of a game over its first turns.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Analysis {
+    pub turns: usize,
+    pub zeros: usize,
+    pub distinct: usize,
+    pub max: usize,
+    pub longest_gap: usize,
+    gap_sum: u64,
+    /// `gaps[0]` counts the turns after a new number, `gaps[i]` the gaps in `2^(i-1)..2^i`
+    pub gaps: Vec<usize>,
+    pub series: Vec<Sample>,
+}
+
+/// The statistics up to `turn`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sample {
+    pub turn: usize,
+    pub zeros: usize,
+    pub distinct: usize,
+    pub max: usize,
+}
+
+/// Plays the first `turns` turns of `game`, which mustn't have been played yet, taking about
+/// `samples` evenly spaced samples.
+///
+/// Every number spoken after the starting ones is the gap since the previous number was spoken
+/// before, or 0 if it was new, so these make up the gap distribution.
+pub fn analyze(game: Game, turns: usize, samples: usize) -> Analysis {
+    debug_assert_eq!(game.turn, 0, "the game has already been played");
+    let start = game.start.len();
+    let every = (turns / samples.max(1)).max(1);
+    // only starting numbers can be at least `turns`, those are kept aside so that a huge one
+    // doesn't blow up `seen`
+    let mut seen: Vec<bool> = Vec::new();
+    let mut seen_large = HashSet::new();
+    let mut analysis = Analysis {
+        turns,
+        zeros: 0,
+        distinct: 0,
+        max: 0,
+        longest_gap: 0,
+        gap_sum: 0,
+        gaps: Vec::new(),
+        series: Vec::new(),
+    };
+
+    for (turn, n) in game.take(turns) {
+        let new = if n < turns {
+            if n >= seen.len() {
+                seen.resize((n + 1).max(seen.len() * 2).min(turns), false);
+            }
+            !std::mem::replace(&mut seen[n], true)
+        } else {
+            seen_large.insert(n)
+        };
+        analysis.distinct += new as usize;
+        analysis.zeros += (n == 0) as usize;
+        analysis.max = analysis.max.max(n);
+
+        if turn > start {
+            let bucket = (usize::BITS - n.leading_zeros()) as usize;
+            if analysis.gaps.len() <= bucket {
+                analysis.gaps.resize(bucket + 1, 0);
+            }
+            analysis.gaps[bucket] += 1;
+            analysis.gap_sum += n as u64;
+            analysis.longest_gap = analysis.longest_gap.max(n);
+        }
+
+        if turn % every == 0 || turn == turns {
+            analysis.series.push(Sample {
+                turn,
+                zeros: analysis.zeros,
+                distinct: analysis.distinct,
+                max: analysis.max,
+            });
+        }
+    }
+    analysis
+}
+
+impl Analysis {
+    pub fn zero_density(&self) -> f64 {
+        self.zeros as f64 / self.turns.max(1) as f64
+    }
+
+    /// Mean of the gaps, not counting the turns after a new number.
+    pub fn mean_gap(&self) -> f64 {
+        let repeats: usize = self.gaps.iter().skip(1).sum();
+        self.gap_sum as f64 / repeats.max(1) as f64
+    }
+
+    pub fn series_csv(&self) -> String {
+        let mut csv = String::from("turn,zeros,zero_density,distinct,max\n");
+        for s in &self.series {
+            let density = s.zeros as f64 / s.turn as f64;
+            writeln!(
+                csv,
+                "{},{},{density},{},{}",
+                s.turn, s.zeros, s.distinct, s.max
+            )
+            .unwrap();
+        }
+        csv
+    }
+
+    pub fn gaps_csv(&self) -> String {
+        let mut csv = String::from("min_gap,max_gap,count\n");
+        for (bucket, count) in self.gaps.iter().enumerate() {
+            let (min, max) = bucket_range(bucket);
+            writeln!(csv, "{min},{max},{count}").unwrap();
+        }
+        csv
+    }
+}
+
+/// The gaps counted by a bucket of [`Analysis::gaps`].
+fn bucket_range(bucket: usize) -> (usize, usize) {
+    match bucket {
+        0 => (0, 0),
+        b => (1 << (b - 1), (1 << b) - 1),
+    }
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const WIDTH: usize = 50;
+
+        writeln!(f, "turns:            {}", self.turns)?;
+        writeln!(
+            f,
+            "zeros:            {} ({:.4} of the turns)",
+            self.zeros,
+            self.zero_density()
+        )?;
+        writeln!(f, "distinct numbers: {}", self.distinct)?;
+        writeln!(f, "largest number:   {}", self.max)?;
+        writeln!(f, "mean gap:         {:.2}", self.mean_gap())?;
+        writeln!(f, "longest gap:      {}", self.longest_gap)?;
+        writeln!(f, "gaps:")?;
+        let most = self.gaps.iter().copied().max().unwrap_or(0).max(1);
+        for (bucket, &count) in self.gaps.iter().enumerate() {
+            let label = match bucket_range(bucket) {
+                (0, 0) => "new".to_owned(),
+                (min, max) if min == max => min.to_string(),
+                (min, max) => format!("{min}-{max}"),
+            };
+            let bar = "#".repeat(count * WIDTH / most);
+            writeln!(f, "  {label:>17} {count:>10} {bar}")?;
+        }
+        Ok(())
+    }
+}
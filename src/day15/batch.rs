@@ -0,0 +1,83 @@
+use std::mem;
+
+use anyhow::Context;
+use rayon::prelude::*;
+
+use super::{Engine, Game, History, NumEntry};
+
+/// A starting seed read from a batch file.
+#[derive(Debug, Clone)]
+pub struct Seed {
+    /// line of the batch file, from 1
+    pub line: usize,
+    pub game: Game,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Worker {
+    pub games: usize,
+    /// size of the largest table the worker allocated
+    pub peak_bytes: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct Batch {
+    /// the number spoken at the last turn by every seed, in input order
+    pub results: Vec<(Seed, usize)>,
+    /// indexed by rayon thread
+    pub workers: Vec<Worker>,
+}
+
+/// Reads one seed per line, skipping blank lines.
+pub fn parse_seeds(input: &str) -> anyhow::Result<Vec<Seed>> {
+    input
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(n, line)| {
+            let game = line.parse().with_context(|| format!("line {}", n + 1))?;
+            Ok(Seed { line: n + 1, game })
+        })
+        .collect()
+}
+
+/// Plays `turns` turns of every seed in parallel, each game with its own flat table.
+///
+/// A worker only plays one game at a time, so its peak memory is the largest table it
+/// allocated.
+pub fn play_all(seeds: Vec<Seed>, turns: usize) -> Batch {
+    let played: Vec<_> = seeds
+        .into_par_iter()
+        .map(|seed| {
+            let mut game = seed.game.clone().with_engine(Engine::Flat);
+            let n = game.play(turns);
+            let worker = rayon::current_thread_index().unwrap_or(0);
+            (seed, n, worker, game.table_bytes())
+        })
+        .collect();
+
+    let mut workers = vec![Worker::default(); rayon::current_num_threads()];
+    let mut results = Vec::with_capacity(played.len());
+    for (seed, n, worker, bytes) in played {
+        if workers.len() <= worker {
+            workers.resize(worker + 1, Worker::default());
+        }
+        workers[worker].games += 1;
+        workers[worker].peak_bytes = workers[worker].peak_bytes.max(bytes);
+        results.push((seed, n));
+    }
+    Batch { results, workers }
+}
+
+impl Game {
+    /// Memory allocated for the history of the game.
+    pub fn table_bytes(&self) -> usize {
+        match &self.history {
+            History::Map(history) => history.capacity() * mem::size_of::<(usize, NumEntry)>(),
+            History::Flat(table) => {
+                table.seen.capacity() * mem::size_of::<u32>()
+                    + table.large.capacity() * mem::size_of::<(usize, u32)>()
+            }
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use thiserror::Error;
+
+use super::{Game, History, NumEntry, Table, MAX_TURNS};
+
+const MAGIC: &[u8; 4] = b"D15G";
+const VERSION: u8 = 1;
+/// magic bytes, version and payload length
+const HEADER: usize = 4 + 1 + 8;
+
+const MAP: u8 = 0;
+const FLAT: u8 = 1;
+
+#[derive(Debug, Error)]
+pub enum CheckpointError {
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    #[error("not a day15 checkpoint")]
+    Magic,
+    #[error("unsupported checkpoint version {0}, expected {VERSION}")]
+    Version(u8),
+    #[error("the checkpoint is truncated")]
+    Truncated,
+    #[error("the checkpoint is corrupted, its checksum doesn't match")]
+    Checksum,
+    #[error("the checkpoint is corrupted: {0}")]
+    Invalid(&'static str),
+}
+
+/// Writes the checkpoint to a temporary file next to `path` first, so that `path` always holds
+/// a complete checkpoint even if we're interrupted.
+pub fn save(game: &Game, path: &Path) -> Result<(), CheckpointError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    fs::write(&tmp, encode(game))?;
+    fs::rename(tmp, path)?;
+    Ok(())
+}
+
+pub fn load(path: &Path) -> Result<Game, CheckpointError> {
+    decode(&fs::read(path)?)
+}
+
+/// Serializes the game as the magic bytes, the format version, the payload length, the payload
+/// and an FNV-1a checksum of the payload. The payload is the game state in LEB128 varints, with
+/// the flat table stored sparsely as the number of zeros before every non-zero turn, followed by
+/// the large numbers kept aside.
+pub fn encode(game: &Game) -> Vec<u8> {
+    let mut out = Vec::new();
+    write_varint(&mut out, game.turn as u64);
+    write_varint(&mut out, game.last_num as u64);
+    write_varint(&mut out, game.start.len() as u64);
+    for &n in &game.start {
+        write_varint(&mut out, n as u64);
+    }
+
+    match &game.history {
+        History::Map(history) => {
+            out.push(MAP);
+            let mut entries: Vec<_> = history.iter().collect();
+            entries.sort_unstable_by_key(|&(&n, _)| n);
+            write_varint(&mut out, entries.len() as u64);
+            for (&n, e) in entries {
+                write_varint(&mut out, n as u64);
+                write_varint(&mut out, e.last_seen as u64);
+                // a number is never repeated in the same turn, so 0 is free to mean `None`
+                write_varint(&mut out, e.turn_diff.unwrap_or(0) as u64);
+            }
+        }
+        History::Flat(table) => {
+            out.push(FLAT);
+            write_varint(&mut out, table.seen.len() as u64);
+            write_varint(
+                &mut out,
+                table.seen.iter().filter(|&&t| t != 0).count() as u64,
+            );
+            let mut zeros = 0;
+            for &t in &table.seen {
+                if t == 0 {
+                    zeros += 1;
+                } else {
+                    write_varint(&mut out, zeros);
+                    write_varint(&mut out, t as u64);
+                    zeros = 0;
+                }
+            }
+            let mut large: Vec<_> = table.large.iter().collect();
+            large.sort_unstable();
+            write_varint(&mut out, large.len() as u64);
+            for (&n, &t) in large {
+                write_varint(&mut out, n as u64);
+                write_varint(&mut out, t as u64);
+            }
+        }
+    }
+
+    frame(&out)
+}
+
+/// Wraps a payload in the header and the checksum.
+pub fn frame(payload: &[u8]) -> Vec<u8> {
+    let mut bytes = MAGIC.to_vec();
+    bytes.push(VERSION);
+    bytes.extend_from_slice(&(payload.len() as u64).to_le_bytes());
+    bytes.extend_from_slice(payload);
+    bytes.extend_from_slice(&fnv1a(payload).to_le_bytes());
+    bytes
+}
+
+pub fn decode(bytes: &[u8]) -> Result<Game, CheckpointError> {
+    if !bytes.starts_with(MAGIC) {
+        return Err(CheckpointError::Magic);
+    }
+    let Some(&version) = bytes.get(MAGIC.len()) else {
+        return Err(CheckpointError::Truncated);
+    };
+    if version != VERSION {
+        return Err(CheckpointError::Version(version));
+    }
+    let Some(len) = bytes.get(MAGIC.len() + 1..HEADER) else {
+        return Err(CheckpointError::Truncated);
+    };
+    let len = u64::from_le_bytes(len.try_into().unwrap());
+    let rest = &bytes[HEADER..];
+    match (rest.len() as u64).cmp(&len.saturating_add(8)) {
+        Ordering::Less => return Err(CheckpointError::Truncated),
+        Ordering::Greater => return Err(CheckpointError::Invalid("trailing bytes")),
+        Ordering::Equal => {}
+    }
+    let (payload, checksum) = rest.split_at(len as usize);
+    if fnv1a(payload) != u64::from_le_bytes(checksum.try_into().unwrap()) {
+        return Err(CheckpointError::Checksum);
+    }
+
+    let mut reader = Reader { bytes: payload };
+    let turn = reader.usize()?;
+    let last_num = reader.usize()?;
+    let start = (0..reader.count()?)
+        .map(|_| reader.usize())
+        .collect::<Result<Vec<_>, _>>()?;
+    if start.is_empty() {
+        return Err(CheckpointError::Invalid("no starting numbers"));
+    }
+    if turn > MAX_TURNS {
+        return Err(CheckpointError::Invalid("too many turns"));
+    }
+    if turn == 0 && last_num != 0 || turn > 0 && start.get(turn - 1).is_some_and(|&n| n != last_num)
+    {
+        return Err(CheckpointError::Invalid(
+            "last number doesn't match the starting numbers",
+        ));
+    }
+
+    let history = match reader.byte()? {
+        MAP => {
+            let mut history = HashMap::new();
+            for _ in 0..reader.count()? {
+                let n = reader.usize()?;
+                let last_seen = reader.usize()?;
+                let turn_diff = Some(reader.usize()?).filter(|&diff| diff != 0);
+                if last_seen == 0 || last_seen > turn || last_seen == turn && n != last_num {
+                    return Err(CheckpointError::Invalid("turn out of range"));
+                }
+                if turn_diff.is_some_and(|diff| diff >= last_seen) {
+                    return Err(CheckpointError::Invalid("gap out of range"));
+                }
+                let entry = NumEntry {
+                    last_seen,
+                    turn_diff,
+                };
+                if history.insert(n, entry).is_some() {
+                    return Err(CheckpointError::Invalid("repeated number"));
+                }
+            }
+            if turn > 0 && history.get(&last_num).is_none_or(|e| e.last_seen != turn) {
+                return Err(CheckpointError::Invalid("the last number is missing"));
+            }
+            History::Map(history)
+        }
+        FLAT => {
+            // the table only grows up to the number of turns played
+            let len = reader.usize()?;
+            if len > turn {
+                return Err(CheckpointError::Invalid("table longer than the game"));
+            }
+            let mut seen = Vec::new();
+            for _ in 0..reader.count()? {
+                let zeros = reader.usize()?;
+                let t = reader.turn(turn)?;
+                if zeros >= len - seen.len() {
+                    return Err(CheckpointError::Invalid("table longer than its length"));
+                }
+                seen.resize(seen.len() + zeros, 0);
+                seen.push(t);
+            }
+            seen.resize(len, 0);
+
+            let mut large = HashMap::new();
+            for _ in 0..reader.count()? {
+                let n = reader.usize()?;
+                let t = reader.turn(turn)?;
+                if n < len || !start.contains(&n) {
+                    return Err(CheckpointError::Invalid(
+                        "large number that isn't set aside",
+                    ));
+                }
+                if large.insert(n, t).is_some() {
+                    return Err(CheckpointError::Invalid("repeated number"));
+                }
+            }
+            History::Flat(Table { seen, large })
+        }
+        _ => return Err(CheckpointError::Invalid("unknown engine")),
+    };
+    if !reader.bytes.is_empty() {
+        return Err(CheckpointError::Invalid("unused payload"));
+    }
+
+    Ok(Game {
+        start,
+        history,
+        turn,
+        last_num,
+    })
+}
+
+fn write_varint(out: &mut Vec<u8>, mut n: u64) {
+    while n >= 0x80 {
+        out.push(n as u8 | 0x80);
+        n >>= 7;
+    }
+    out.push(n as u8);
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn byte(&mut self) -> Result<u8, CheckpointError> {
+        let (&b, rest) = self
+            .bytes
+            .split_first()
+            .ok_or(CheckpointError::Invalid("unexpected end of the payload"))?;
+        self.bytes = rest;
+        Ok(b)
+    }
+
+    fn usize(&mut self) -> Result<usize, CheckpointError> {
+        let mut n = 0u64;
+        for shift in (0..64).step_by(7) {
+            let b = self.byte()?;
+            n |= ((b & 0x7f) as u64) << shift;
+            if b & 0x80 == 0 {
+                return usize::try_from(n)
+                    .map_err(|_| CheckpointError::Invalid("number too large"));
+            }
+        }
+        Err(CheckpointError::Invalid("varint too long"))
+    }
+
+    /// Reads the number of items that follow, every item takes at least one byte.
+    fn count(&mut self) -> Result<usize, CheckpointError> {
+        let count = self.usize()?;
+        if count > self.bytes.len() {
+            return Err(CheckpointError::Invalid("more items than bytes left"));
+        }
+        Ok(count)
+    }
+
+    /// Reads a turn of the flat table, which must be a turn before `turn`.
+    fn turn(&mut self, turn: usize) -> Result<u32, CheckpointError> {
+        match self.usize()? {
+            t if t == 0 || t >= turn => Err(CheckpointError::Invalid("turn out of range")),
+            t => Ok(t as u32),
+        }
+    }
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x0000_0100_0000_01b3)
+    })
+}
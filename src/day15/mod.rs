@@ -0,0 +1,835 @@
+use crate::{parse_arg, Solution};
+use anyhow::{anyhow, bail, Context};
+use std::{
+    env::args,
+    fs,
+    num::ParseIntError,
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Instant,
+};
+use thiserror::Error;
+
+mod analysis;
+mod batch;
+mod checkpoint;
+
+use std::collections::HashMap;
+
+const USAGE: &str = "\
+usage: day15 <path> [--turns <n>] [--engine map|flat] [--bench]
+       day15 --start <numbers> [--turns <n>] [--engine map|flat] [--bench]
+       day15 <path> | --start <numbers> --checkpoint <file> [--every <k>] [--turns <n>]
+       day15 <path> | --start <numbers> --analyze [--samples <k>] [--csv series|gaps] [--turns <n>]
+       day15 --batch <file> [--turns <n>]
+
+the starting numbers are a comma separated list, e.g. `0,3,6`, either in the
+file at <path> or given directly; without `--turns` both parts are solved
+
+with `--checkpoint` the game is saved to <file> every <k> turns (default
+1000000) and resumed from it if it exists; it plays 30000000 turns by default
+
+`--analyze` reports statistics of the sequence, sampled <k> times (default 100),
+optionally as CSV: the samples with `series` or the gap histogram with `gaps`
+
+`--batch` plays the starting numbers on every line of <file> in parallel, for
+30000000 turns by default";
+
+/// Entry point of the `day15` binary.
+pub fn main() -> anyhow::Result<()> {
+    let mut args = args().skip(1);
+    let mut input = None;
+    let mut turns = None;
+    let mut engine = Engine::Flat;
+    let mut bench = false;
+    let mut checkpoint = None;
+    let mut every = 1_000_000;
+    let mut analyze = false;
+    let mut samples = 100;
+    let mut csv = None;
+    let mut batch = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--start" => {
+                let nums = args
+                    .next()
+                    .ok_or_else(|| anyhow!("`--start` expects a value\n{USAGE}"))?;
+                input = Some(nums);
+            }
+            "--turns" => turns = Some(parse_turns(args.next())?),
+            "--engine" => {
+                engine = match args.next().as_deref() {
+                    Some("map") => Engine::Map,
+                    Some("flat") => Engine::Flat,
+                    _ => bail!("`--engine` expects map or flat\n{USAGE}"),
+                }
+            }
+            "--bench" => bench = true,
+            "--checkpoint" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("`--checkpoint` expects a path\n{USAGE}"))?;
+                checkpoint = Some(PathBuf::from(path));
+            }
+            "--every" => every = parse_arg(args.next(), "--every")?,
+            "--analyze" => analyze = true,
+            "--samples" => samples = parse_arg(args.next(), "--samples")?,
+            "--csv" => {
+                csv = match args.next().as_deref() {
+                    Some("series") => Some(Csv::Series),
+                    Some("gaps") => Some(Csv::Gaps),
+                    _ => bail!("`--csv` expects series or gaps\n{USAGE}"),
+                }
+            }
+            "--batch" => {
+                let path = args
+                    .next()
+                    .ok_or_else(|| anyhow!("`--batch` expects a path\n{USAGE}"))?;
+                batch = Some(path);
+            }
+            _ if input.is_none() => {
+                let nums =
+                    fs::read_to_string(&arg).with_context(|| format!("can't read `{arg}`"))?;
+                input = Some(nums);
+            }
+            x => bail!("unexpected argument: `{x}`\n{USAGE}"),
+        }
+    }
+    if let Some(path) = batch {
+        let seeds = fs::read_to_string(&path).with_context(|| format!("can't read `{path}`"))?;
+        let seeds =
+            batch::parse_seeds(&seeds).with_context(|| format!("invalid seed in `{path}`"))?;
+        run_batch(seeds, turns.unwrap_or(30_000_000));
+        return Ok(());
+    }
+
+    let Some(input) = input else {
+        bail!("please provide the starting numbers\n{USAGE}")
+    };
+    let game = input.trim().parse::<Game>()?;
+
+    if bench {
+        let turns = turns.unwrap_or(30_000_000);
+        for engine in [Engine::Map, Engine::Flat] {
+            let start = Instant::now();
+            let ans = game.clone().with_engine(engine).play(turns);
+            println!("{engine:?}: {ans} in {:.2?}", start.elapsed());
+        }
+        return Ok(());
+    }
+
+    if analyze {
+        let turns = turns.unwrap_or(30_000_000);
+        let analysis = analysis::analyze(game.with_engine(Engine::Flat), turns, samples);
+        match csv {
+            Some(Csv::Series) => print!("{}", analysis.series_csv()),
+            Some(Csv::Gaps) => print!("{}", analysis.gaps_csv()),
+            None => print!("{analysis}"),
+        }
+        return Ok(());
+    }
+
+    if let Some(path) = checkpoint {
+        if every == 0 {
+            bail!("`--every` must be positive");
+        }
+        let turns = turns.unwrap_or(30_000_000);
+        let ans = play_with_checkpoints(game.with_engine(engine), &path, every, turns)?;
+        println!("Turn {turns}: {ans}");
+        return Ok(());
+    }
+
+    let game = game.with_engine(engine);
+    match turns {
+        Some(turns) => println!("Turn {turns}: {}", game.clone().play(turns)),
+        None => {
+            println!("Part1: {}", Day15::part1(&game)?);
+            println!("Part2: {}", Day15::part2(&game)?);
+        }
+    }
+    Ok(())
+}
+
+/// Parses the value of `--turns`, which must be in `1..=MAX_TURNS`.
+fn parse_turns(arg: Option<String>) -> anyhow::Result<usize> {
+    let turns = parse_arg(arg, "--turns")?;
+    if !(1..=MAX_TURNS).contains(&turns) {
+        bail!("`--turns` must be between 1 and {MAX_TURNS}, got {turns}");
+    }
+    Ok(turns)
+}
+
+fn run_batch(seeds: Vec<batch::Seed>, turns: usize) {
+    let start = Instant::now();
+    let batch = batch::play_all(seeds, turns);
+    for (seed, ans) in &batch.results {
+        let nums = seed.game.start.iter().map(|n| n.to_string());
+        let nums = nums.collect::<Vec<_>>().join(",");
+        println!("line {} ({nums}): turn {turns}: {ans}", seed.line);
+    }
+    println!("{} games in {:.2?}", batch.results.len(), start.elapsed());
+    for (i, worker) in batch.workers.iter().enumerate() {
+        let mib = worker.peak_bytes as f64 / (1 << 20) as f64;
+        println!(
+            "worker {i}: {} games, peak table {mib:.1} MiB",
+            worker.games
+        );
+    }
+}
+
+enum Csv {
+    Series,
+    Gaps,
+}
+
+/// Plays `turns` turns, saving the game to `path` every `every` turns. If `path` already holds
+/// a checkpoint of the same game, the game is resumed from there.
+fn play_with_checkpoints(
+    game: Game,
+    path: &Path,
+    every: usize,
+    turns: usize,
+) -> anyhow::Result<usize> {
+    let mut game = if path.exists() {
+        let engine = game.engine();
+        let saved = checkpoint::load(path)
+            .with_context(|| format!("can't resume from `{}`", path.display()))?;
+        if saved.start != game.start {
+            bail!(
+                "`{}` is a checkpoint of the game starting with {:?}",
+                path.display(),
+                saved.start
+            );
+        }
+        if saved.turn > turns {
+            bail!(
+                "`{}` is a checkpoint at turn {}, past turn {turns}",
+                path.display(),
+                saved.turn
+            );
+        }
+        println!("Resuming from turn {}", saved.turn);
+        saved.with_engine(engine)
+    } else {
+        game
+    };
+
+    while game.turn < turns {
+        let next = (game.turn / every + 1) * every;
+        game.play(next.min(turns));
+        checkpoint::save(&game, path)
+            .with_context(|| format!("can't save the checkpoint to `{}`", path.display()))?;
+    }
+    Ok(game.last_num)
+}
+
+pub struct Day15;
+
+impl Solution for Day15 {
+    type Input = Game;
+    type Output = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.trim().parse::<Game>()?.with_engine(Engine::Flat))
+    }
+
+    fn part1(game: &Self::Input) -> anyhow::Result<usize> {
+        Ok(game.clone().play(2020))
+    }
+
+    fn part2(game: &Self::Input) -> anyhow::Result<usize> {
+        Ok(game.clone().play(30_000_000))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+struct NumEntry {
+    last_seen: usize,
+    turn_diff: Option<usize>,
+}
+
+/// How a [`Game`] remembers when every number was last spoken.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Engine {
+    /// A `HashMap` with an entry for every spoken number.
+    Map,
+    /// A table indexed by number, preallocated for all turns of [`Game::play`]. Every number
+    /// spoken after the starting ones is smaller than the turn count, larger starting numbers
+    /// are kept aside.
+    Flat,
+}
+
+/// The most turns a game can be played for, the flat engine stores turns as `u32`.
+pub const MAX_TURNS: usize = u32::MAX as usize;
+
+#[derive(Debug, Clone)]
+enum History {
+    Map(HashMap<usize, NumEntry>),
+    Flat(Table),
+}
+
+/// The turn every number was spoken at before the current turn, 0 if never.
+#[derive(Debug, Clone, Default)]
+struct Table {
+    /// indexed by number
+    seen: Vec<u32>,
+    /// numbers past the end of `seen`, which can only be starting numbers since every other
+    /// number is smaller than the turn it's spoken at
+    large: HashMap<usize, u32>,
+}
+
+impl Table {
+    fn get(&self, n: usize) -> u32 {
+        match self.seen.get(n) {
+            Some(&turn) => turn,
+            None => self.large.get(&n).copied().unwrap_or(0),
+        }
+    }
+
+    /// Records that `n` was spoken at `turn`, returning the turn it was spoken at before. Only
+    /// numbers up to `turn` get a slot in `seen`, so a huge starting number doesn't blow up
+    /// the table.
+    fn set(&mut self, n: usize, turn: usize) -> u32 {
+        if n >= self.seen.len() && n <= turn {
+            self.grow(n + 1);
+        }
+        match self.seen.get_mut(n) {
+            Some(t) => std::mem::replace(t, turn as u32),
+            None => self.large.insert(n, turn as u32).unwrap_or(0),
+        }
+    }
+
+    /// Extends `seen` to `len` numbers, moving over the entries of `large` that now fit.
+    fn grow(&mut self, len: usize) {
+        if self.seen.len() >= len {
+            return;
+        }
+        self.seen.resize(len, 0);
+        self.large.retain(|&n, &mut turn| {
+            if n < len {
+                self.seen[n] = turn;
+                false
+            } else {
+                true
+            }
+        });
+    }
+
+    /// The numbers that have been spoken, with their turn.
+    fn iter(&self) -> impl Iterator<Item = (usize, u32)> + '_ {
+        let seen = self.seen.iter().enumerate().map(|(n, &turn)| (n, turn));
+        let large = self.large.iter().map(|(&n, &turn)| (n, turn));
+        seen.chain(large).filter(|&(_, turn)| turn != 0)
+    }
+}
+
+/// The memory game, an iterator over the `(turn, number)` pairs spoken from turn 1 on.
+#[derive(Debug, Clone)]
+pub struct Game {
+    /// the starting numbers, spoken in the first turns
+    start: Vec<usize>,
+    history: History,
+    /// number of turns spoken so far
+    turn: usize,
+    last_num: usize,
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ParseGameError {
+    #[error("the game needs at least one starting number")]
+    Empty,
+    #[error("invalid starting number `{0}`")]
+    Number(String, #[source] ParseIntError),
+}
+
+impl FromStr for Game {
+    type Err = ParseGameError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(ParseGameError::Empty);
+        }
+        let start = s
+            .split(',')
+            .map(|s| {
+                let s = s.trim();
+                s.parse::<usize>()
+                    .map_err(|e| ParseGameError::Number(s.to_owned(), e))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Game {
+            start,
+            history: History::Map(HashMap::new()),
+            turn: 0,
+            last_num: 0,
+        })
+    }
+}
+
+impl Game {
+    pub fn engine(&self) -> Engine {
+        match self.history {
+            History::Map(_) => Engine::Map,
+            History::Flat(_) => Engine::Flat,
+        }
+    }
+
+    /// Moves the game state over to `engine`.
+    pub fn with_engine(self, engine: Engine) -> Self {
+        let history = match (self.history, engine) {
+            (History::Map(history), Engine::Flat) => {
+                let mut table = Table::default();
+                for (&n, e) in &history {
+                    let turn = if n == self.last_num {
+                        e.turn_diff.map_or(0, |diff| e.last_seen - diff)
+                    } else {
+                        e.last_seen
+                    };
+                    if turn != 0 {
+                        table.set(n, turn);
+                    }
+                }
+                History::Flat(table)
+            }
+            (History::Flat(table), Engine::Map) => {
+                let mut history: HashMap<usize, NumEntry> = table
+                    .iter()
+                    .map(|(n, turn)| {
+                        let entry = NumEntry {
+                            last_seen: turn as usize,
+                            turn_diff: None,
+                        };
+                        (n, entry)
+                    })
+                    .collect();
+                if self.turn > 0 {
+                    let previous = table.get(self.last_num) as usize;
+                    let entry = NumEntry {
+                        last_seen: self.turn,
+                        turn_diff: (previous != 0).then(|| self.turn - previous),
+                    };
+                    history.insert(self.last_num, entry);
+                }
+                History::Map(history)
+            }
+            (history, _) => history,
+        };
+        Self { history, ..self }
+    }
+
+    /// Speaks the number of the next turn and returns it.
+    fn next_turn(&mut self) -> usize {
+        let n = match self.start.get(self.turn) {
+            Some(&n) => n,
+            None => match &self.history {
+                History::Map(history) => history[&self.last_num].turn_diff.unwrap_or(0),
+                History::Flat(table) => match table.get(self.last_num) {
+                    0 => 0,
+                    previous => self.turn - previous as usize,
+                },
+            },
+        };
+
+        match &mut self.history {
+            History::Map(_) => {
+                self.turn += 1;
+                self.update_entry(n);
+            }
+            History::Flat(table) => {
+                assert!(self.turn < MAX_TURNS, "too many turns");
+                if self.turn > 0 {
+                    table.set(self.last_num, self.turn);
+                }
+                self.turn += 1;
+                self.last_num = n;
+            }
+        }
+        n
+    }
+
+    fn update_entry(&mut self, n: usize) {
+        let History::Map(history) = &mut self.history else {
+            unreachable!("only used by the map engine")
+        };
+        history
+            .entry(n)
+            .and_modify(|e| {
+                e.turn_diff = Some(self.turn - e.last_seen);
+                e.last_seen = self.turn;
+            })
+            .or_insert({
+                NumEntry {
+                    last_seen: self.turn,
+                    turn_diff: None,
+                }
+            });
+        self.last_num = n;
+    }
+
+    /// Plays until turn `num_turns`, which must not be past [`MAX_TURNS`], and returns the
+    /// number spoken last.
+    fn play(&mut self, num_turns: usize) -> usize {
+        // once the starting numbers have been spoken every number is smaller than its turn,
+        // which lets the flat engine index its table without any checks
+        while self.turn < num_turns && (self.turn < self.start.len() || self.last_num >= num_turns)
+        {
+            self.next_turn();
+        }
+        match &mut self.history {
+            History::Map(_) => {
+                while self.turn < num_turns {
+                    self.next_turn();
+                }
+            }
+            History::Flat(table) => {
+                assert!(num_turns <= MAX_TURNS, "too many turns");
+                table.grow(num_turns);
+                let seen = &mut table.seen;
+                let mut last = self.last_num;
+                for turn in self.turn..num_turns {
+                    let previous = std::mem::replace(&mut seen[last], turn as u32);
+                    last = match previous {
+                        0 => 0,
+                        previous => turn - previous as usize,
+                    };
+                }
+                self.turn = self.turn.max(num_turns);
+                self.last_num = last;
+            }
+        }
+        self.last_num
+    }
+
+    /// The first turn up to `limit` at which `num` is spoken, not counting the turns that have
+    /// already been played.
+    pub fn first_turn_of(&self, num: usize, limit: usize) -> Option<usize> {
+        self.clone()
+            .take_while(|&(turn, _)| turn <= limit)
+            .find(|&(_, n)| n == num)
+            .map(|(turn, _)| turn)
+    }
+
+    /// Number of distinct numbers spoken by the end of `turn`.
+    pub fn distinct_by(&self, turn: usize) -> usize {
+        let mut game = self.clone();
+        game.play(turn);
+        match &game.history {
+            History::Map(history) => history.len(),
+            History::Flat(table) => {
+                let first_time = game.turn > 0 && table.get(game.last_num) == 0;
+                table.iter().count() + first_time as usize
+            }
+        }
+    }
+
+    /// The longest gap between two turns speaking the same number, seen up to turn `limit`,
+    /// with the turn that spoke it. Like [`Game::first_turn_of`] this only looks at turns that
+    /// haven't been played yet.
+    pub fn longest_gap(&self, limit: usize) -> Option<(usize, usize)> {
+        // every number spoken after the starting ones is the gap of the one before it
+        self.clone()
+            .take_while(|&(turn, _)| turn <= limit)
+            .filter(|&(turn, _)| turn > self.start.len())
+            .max_by_key(|&(turn, gap)| (gap, std::cmp::Reverse(turn)))
+    }
+}
+
+impl Iterator for Game {
+    type Item = (usize, usize);
+
+    /// Plays one turn, the game never ends.
+    fn next(&mut self) -> Option<Self::Item> {
+        let n = self.next_turn();
+        Some((self.turn, n))
+    }
+}
+
+#[test]
+fn engines_agree() {
+    let examples = [
+        ("0,3,6", 436),
+        ("1,3,2", 1),
+        ("2,1,3", 10),
+        ("1,2,3", 27),
+        ("2,3,1", 78),
+        ("3,2,1", 438),
+        ("3,1,2", 1836),
+    ];
+    for (nums, ans) in examples {
+        let game: Game = nums.parse().unwrap();
+        assert_eq!(game.clone().play(2020), ans, "{nums}");
+        assert_eq!(
+            game.clone().with_engine(Engine::Flat).play(2020),
+            ans,
+            "{nums}"
+        );
+
+        // switching engines in the middle of a game
+        let mut game = game.with_engine(Engine::Flat);
+        game.play(1000);
+        let mut game = game.with_engine(Engine::Map);
+        game.play(1500);
+        assert_eq!(game.with_engine(Engine::Flat).play(2020), ans, "{nums}");
+    }
+
+    let mut map: Game = "0,3,6".parse().unwrap();
+    let mut flat = map.clone().with_engine(Engine::Flat);
+    for turns in [3, 10, 1000, 50_000] {
+        assert_eq!(map.play(turns), flat.play(turns), "{turns}");
+    }
+
+    // starting numbers larger than the turn count stay out of the flat table
+    for nums in ["0,5000000000", "5000000000,7,5000000000", "3,1,4000000000"] {
+        let game: Game = nums.parse().unwrap();
+        let flat = game.clone().with_engine(Engine::Flat);
+        assert!(
+            flat.clone().take(3000).eq(game.clone().take(3000)),
+            "{nums}"
+        );
+        for turns in [3, 4, 10, 3000] {
+            let ans = game.clone().play(turns);
+            assert_eq!(flat.clone().play(turns), ans, "{nums} {turns}");
+        }
+
+        let mut played = flat.clone();
+        played.play(10);
+        let History::Flat(table) = &played.history else {
+            unreachable!()
+        };
+        assert_eq!(table.seen.len(), 10, "{nums}");
+        let mut switched = played.with_engine(Engine::Map);
+        assert_eq!(switched.play(3000), game.clone().play(3000), "{nums}");
+    }
+    let game: Game = "0,5000000000".parse().unwrap();
+    assert_eq!(game.with_engine(Engine::Flat).play(10), 4);
+}
+
+#[test]
+fn parse_game() {
+    let game: Game = " 0, 3,6\n".parse().unwrap();
+    assert_eq!(game.clone().play(2020), 436);
+    assert_eq!("".parse::<Game>().unwrap_err(), ParseGameError::Empty);
+    assert_eq!(" \n".parse::<Game>().unwrap_err(), ParseGameError::Empty);
+    assert!(matches!(
+        "1,,2".parse::<Game>(),
+        Err(ParseGameError::Number(s, _)) if s.is_empty()
+    ));
+    assert!(matches!(
+        "1,x".parse::<Game>(),
+        Err(ParseGameError::Number(s, _)) if s == "x"
+    ));
+}
+
+#[test]
+fn turns_range() {
+    assert_eq!(parse_turns(Some("2020".to_owned())).unwrap(), 2020);
+    assert_eq!(
+        parse_turns(Some("4294967295".to_owned())).unwrap(),
+        MAX_TURNS
+    );
+    for turns in ["0", "4294967296", "5000000000"] {
+        let err = parse_turns(Some(turns.to_owned())).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!("`--turns` must be between 1 and 4294967295, got {turns}")
+        );
+    }
+    let err = parse_turns(Some("-1".to_owned())).unwrap_err();
+    assert_eq!(err.to_string(), "invalid value for `--turns`: `-1`");
+    assert!(parse_turns(None).is_err());
+}
+
+#[test]
+fn iterate() {
+    use itertools::Itertools;
+
+    for engine in [Engine::Map, Engine::Flat] {
+        let game = "0,3,6".parse::<Game>().unwrap().with_engine(engine);
+        let nums: Vec<usize> = game.clone().map(|(_, n)| n).take(10).collect();
+        assert_eq!(nums, [0, 3, 6, 0, 3, 3, 1, 0, 4, 0]);
+        assert_eq!(game.clone().nth(2019), Some((2020, 436)));
+
+        let repeats = game
+            .clone()
+            .take(10)
+            .tuple_windows()
+            .filter(|(a, b)| a.1 == b.1);
+        assert_eq!(repeats.count(), 1);
+
+        assert_eq!(game.first_turn_of(4, 100), Some(9));
+        assert_eq!(game.first_turn_of(4, 8), None);
+        assert_eq!(game.distinct_by(10), 5);
+        assert_eq!(game.distinct_by(7), 4);
+        assert_eq!(game.longest_gap(10), Some((9, 4)));
+        assert_eq!(game.longest_gap(3), None);
+
+        // iterating continues where `play` stopped
+        let mut played = game.clone();
+        played.play(8);
+        assert_eq!(played.next(), Some((9, 4)));
+        assert_eq!(played.first_turn_of(0, 10), Some(10));
+    }
+}
+
+#[test]
+fn checkpoints() {
+    use checkpoint::{decode, encode, frame, CheckpointError};
+
+    for engine in [Engine::Map, Engine::Flat] {
+        let mut game = "0,3,6".parse::<Game>().unwrap().with_engine(engine);
+        let fresh = decode(&encode(&game)).unwrap();
+        assert_eq!(fresh.clone().play(2020), 436);
+
+        game.play(1000);
+        let bytes = encode(&game);
+        let mut resumed = decode(&bytes).unwrap();
+        assert_eq!(resumed.engine(), engine);
+        assert_eq!(resumed.turn, 1000);
+        assert_eq!(resumed.play(2020), 436);
+
+        for len in [0, 3, 5, 12, bytes.len() / 2, bytes.len() - 1] {
+            let err = decode(&bytes[..len]).unwrap_err();
+            assert!(
+                matches!(err, CheckpointError::Truncated | CheckpointError::Magic),
+                "{len}: {err}"
+            );
+        }
+        let mut corrupted = bytes.clone();
+        corrupted[20] ^= 1;
+        assert!(matches!(decode(&corrupted), Err(CheckpointError::Checksum)));
+        let mut newer = bytes.clone();
+        newer[4] = 2;
+        assert!(matches!(decode(&newer), Err(CheckpointError::Version(2))));
+    }
+
+    let mut game = "3,5000000000,3"
+        .parse::<Game>()
+        .unwrap()
+        .with_engine(Engine::Flat);
+    for turns in [1, 2, 10] {
+        game.play(turns);
+        let mut resumed = decode(&encode(&game)).unwrap();
+        assert_eq!(resumed.play(3000), game.clone().play(3000), "{turns}");
+    }
+
+    // states no game can reach are rejected instead of panicking once resumed
+    let invalid = |bytes: &[u8]| match decode(bytes) {
+        Err(CheckpointError::Invalid(msg)) => msg,
+        other => panic!("{other:?}"),
+    };
+    let played = |engine, turns| {
+        let mut game = "0,3,6".parse::<Game>().unwrap().with_engine(engine);
+        game.play(turns);
+        game
+    };
+    let mut game = played(Engine::Flat, 1000);
+    game.turn = 500;
+    assert_eq!(invalid(&encode(&game)), "table longer than the game");
+    let mut game = played(Engine::Map, 1000);
+    game.turn = 500;
+    assert_eq!(invalid(&encode(&game)), "turn out of range");
+    let mut game = played(Engine::Map, 1000);
+    let History::Map(history) = &mut game.history else {
+        unreachable!()
+    };
+    history.remove(&game.last_num);
+    assert_eq!(invalid(&encode(&game)), "the last number is missing");
+    let mut game = played(Engine::Flat, 2);
+    game.last_num = 6;
+    assert_eq!(
+        invalid(&encode(&game)),
+        "last number doesn't match the starting numbers"
+    );
+    let mut game = played(Engine::Flat, 10);
+    let History::Flat(table) = &mut game.history else {
+        unreachable!()
+    };
+    table.large.insert(7777, 1);
+    assert_eq!(invalid(&encode(&game)), "large number that isn't set aside");
+    // turn 10, last number 0, starting numbers [0], a flat table of 5 with one huge run
+    let huge_run = [10, 0, 1, 0, 1, 5, 1, 0x80, 0x80, 0x80, 0x80, 0x10, 3, 0];
+    assert_eq!(invalid(&frame(&huge_run)), "table longer than its length");
+    let huge_start = [10, 0, 0xff, 0xff, 0xff, 0xff, 0x0f];
+    assert_eq!(invalid(&frame(&huge_start)), "more items than bytes left");
+
+    let dir = std::env::temp_dir().join(format!("day15-checkpoint-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("game.bin");
+    let game: Game = "0,3,6".parse().unwrap();
+    assert_eq!(
+        play_with_checkpoints(game.clone(), &path, 300, 1000).unwrap(),
+        5
+    );
+    assert_eq!(checkpoint::load(&path).unwrap().turn, 1000);
+    assert_eq!(
+        play_with_checkpoints(game.clone(), &path, 300, 2020).unwrap(),
+        436
+    );
+    let other: Game = "1,3,2".parse().unwrap();
+    assert!(play_with_checkpoints(other, &path, 300, 2020).is_err());
+    assert!(play_with_checkpoints(game.clone(), &path, 300, 1000).is_err());
+
+    // the temporary file is named after the whole file name, even if that ends in `.tmp`
+    let path = dir.join("game.tmp");
+    assert_eq!(play_with_checkpoints(game, &path, 1, 10).unwrap(), 0);
+    assert_eq!(checkpoint::load(&path).unwrap().turn, 10);
+    assert!(!dir.join("game.tmp.tmp").exists());
+    fs::remove_dir_all(dir).unwrap();
+}
+
+#[test]
+fn analyze() {
+    let game: Game = "0,3,6".parse().unwrap();
+    let analysis = analysis::analyze(game.clone(), 10, 5);
+    assert_eq!(analysis.zeros, 4);
+    assert_eq!(analysis.distinct, 5);
+    assert_eq!(analysis.max, 6);
+    assert_eq!(analysis.longest_gap, 4);
+    assert_eq!(analysis.gaps, [3, 1, 2, 1]);
+    assert_eq!(analysis.mean_gap(), 11.0 / 4.0);
+    assert_eq!(
+        analysis.series_csv(),
+        "turn,zeros,zero_density,distinct,max\n\
+         2,1,0.5,2,3\n\
+         4,2,0.5,3,6\n\
+         6,2,0.3333333333333333,3,6\n\
+         8,3,0.375,4,6\n\
+         10,4,0.4,5,6\n"
+    );
+    assert_eq!(
+        analysis.gaps_csv(),
+        "min_gap,max_gap,count\n0,0,3\n1,1,1\n2,3,2\n4,7,1\n"
+    );
+
+    let analysis = analysis::analyze(game, 30_000, 7);
+    assert_eq!(analysis.series.len(), 8);
+    assert_eq!(analysis.series.last().unwrap().turn, 30_000);
+    assert_eq!(analysis.gaps.iter().sum::<usize>(), 30_000 - 3);
+
+    let game: Game = "5000000000,7,5000000000".parse().unwrap();
+    let analysis = analysis::analyze(game, 10, 1);
+    assert_eq!(analysis.distinct, 6);
+    assert_eq!(analysis.max, 5_000_000_000);
+    let game: Game = "3,5000000000,3".parse().unwrap();
+    assert_eq!(
+        analysis::analyze(game.clone(), 10, 1).distinct,
+        game.distinct_by(10)
+    );
+}
+
+#[test]
+fn batch() {
+    let seeds = batch::parse_seeds("0,3,6\n\n1,3,2\n2,1,3\n").unwrap();
+    assert_eq!(seeds.iter().map(|s| s.line).collect::<Vec<_>>(), [1, 3, 4]);
+    let err = batch::parse_seeds("0,3,6\n1,x\n").unwrap_err();
+    assert_eq!(err.to_string(), "line 2");
+
+    let batch = batch::play_all(seeds, 2020);
+    let results: Vec<_> = batch.results.iter().map(|(s, n)| (s.line, *n)).collect();
+    assert_eq!(results, [(1, 436), (3, 1), (4, 10)]);
+    assert_eq!(batch.workers.iter().map(|w| w.games).sum::<usize>(), 3);
+    // the tables may have spare capacity, depending on how they grew
+    let peak = batch.workers.iter().map(|w| w.peak_bytes).max().unwrap();
+    assert!(peak >= 2020 * 4, "{peak}");
+}
@@ -1,5 +1,7 @@
 use std::{mem, ops::Range};
 
+use crate::Solution;
+use anyhow::bail;
 use itertools::iproduct;
 
 const CYCLES: usize = 6;
@@ -10,21 +12,57 @@ const W: usize = WIDTH + 2 * CYCLES + 2;
 const H: usize = HEIGHT + 2 * CYCLES + 2;
 const D: usize = 1 + 2 * CYCLES + 2;
 
-fn main() {
-    simulate3d();
-    simulate4d();
+/// Entry point of the `day17` binary.
+pub fn main() -> anyhow::Result<()> {
+    let grid = Day17::parse(INITIAL_STATE)?;
+    println!("{}", simulate3d(&grid));
+    println!("{}", simulate4d(&grid));
+    Ok(())
 }
 
-fn simulate3d() {
+pub struct Day17;
+
+impl Solution for Day17 {
+    type Input = Vec<Vec<bool>>;
+    type Output = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        let mut grid = Vec::new();
+        for line in input.lines() {
+            if line.len() > WIDTH {
+                bail!("the initial state can be at most {WIDTH} cells wide");
+            }
+            let row = line
+                .bytes()
+                .map(|byte| match byte {
+                    b'.' => Ok(false),
+                    b'#' => Ok(true),
+                    x => bail!("unexpected cell: `{}`", x as char),
+                })
+                .collect::<anyhow::Result<_>>()?;
+            grid.push(row);
+        }
+        if grid.len() > HEIGHT {
+            bail!("the initial state can be at most {HEIGHT} cells high");
+        }
+        Ok(grid)
+    }
+
+    fn part1(grid: &Self::Input) -> anyhow::Result<usize> {
+        Ok(simulate3d(grid))
+    }
+
+    fn part2(grid: &Self::Input) -> anyhow::Result<usize> {
+        Ok(simulate4d(grid))
+    }
+}
+
+fn simulate3d(grid: &[Vec<bool>]) -> usize {
     let mut buf1 = [[[false; W]; H]; D];
     let mut buf2 = [[[false; W]; H]; D];
-    INITIAL_STATE.lines().enumerate().for_each(|(i, line)| {
-        line.bytes().enumerate().for_each(|(j, byte)| {
-            buf1[CYCLES + 1][j + CYCLES + 1][i + CYCLES + 1] = match byte {
-                b'.' => false,
-                b'#' => true,
-                _ => unreachable!(),
-            }
+    grid.iter().enumerate().for_each(|(i, line)| {
+        line.iter().enumerate().for_each(|(j, cell)| {
+            buf1[CYCLES + 1][j + CYCLES + 1][i + CYCLES + 1] = *cell;
         })
     });
 
@@ -40,8 +78,7 @@ fn simulate3d() {
         mem::swap(&mut buf1, &mut buf2);
     }
 
-    let n_active = buf1.iter().flatten().flatten().filter(|x| **x).count();
-    println!("{n_active}");
+    buf1.iter().flatten().flatten().filter(|x| **x).count()
 }
 
 fn count_nbors3d(space: [[[bool; W]; H]; D], i: usize, j: usize, k: usize) -> usize {
@@ -57,16 +94,12 @@ fn count_nbors3d(space: [[[bool; W]; H]; D], i: usize, j: usize, k: usize) -> us
     active_nbors - cell_is_active
 }
 
-fn simulate4d() {
+fn simulate4d(grid: &[Vec<bool>]) -> usize {
     let mut buf1 = [[[[false; W]; H]; D]; D];
     let mut buf2 = [[[[false; W]; H]; D]; D];
-    INITIAL_STATE.lines().enumerate().for_each(|(i, line)| {
-        line.bytes().enumerate().for_each(|(j, byte)| {
-            buf1[CYCLES + 1][CYCLES + 1][j + CYCLES + 1][i + CYCLES + 1] = match byte {
-                b'.' => false,
-                b'#' => true,
-                _ => unreachable!(),
-            }
+    grid.iter().enumerate().for_each(|(i, line)| {
+        line.iter().enumerate().for_each(|(j, cell)| {
+            buf1[CYCLES + 1][CYCLES + 1][j + CYCLES + 1][i + CYCLES + 1] = *cell;
         })
     });
 
@@ -85,14 +118,12 @@ fn simulate4d() {
         mem::swap(&mut buf1, &mut buf2);
     }
 
-    let n_active = buf1
-        .iter()
+    buf1.iter()
         .flatten()
         .flatten()
         .flatten()
         .filter(|x| **x)
-        .count();
-    println!("{n_active}");
+        .count()
 }
 
 fn count_nbors4d(space: [[[[bool; W]; H]; D]; D], i: usize, j: usize, k: usize, l: usize) -> usize {
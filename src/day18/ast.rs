@@ -0,0 +1,286 @@
+use std::{iter::Peekable, vec::IntoIter};
+
+use super::{error::ExprError, num::Number, Assoc, Precedence, Token, Vars};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Expr {
+    Num(i64),
+    BinOp(char, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    /// A variable and the column it's referenced at.
+    Var(String, usize),
+    /// A parenthesized expression, kept so that the tree mirrors the input.
+    Group(Box<Expr>),
+}
+
+/// A line of a program.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Stmt {
+    /// `let name = expr`
+    Let(String, Expr),
+    Expr(Expr),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Lexeme {
+    Num(i64),
+    Var(String),
+    Let,
+    Assign,
+    Op(char),
+    Open,
+    Close,
+}
+
+/// Splits `input` into lexemes, each paired with the column it starts at.
+fn lex(input: &str) -> Result<Vec<(usize, Lexeme)>, ExprError> {
+    let mut lexemes = Vec::new();
+    let mut chars = input
+        .chars()
+        .enumerate()
+        .map(|(i, c)| (i + 1, c))
+        .peekable();
+    while let Some((col, c)) = chars.next() {
+        let lexeme = match c {
+            c if c.is_whitespace() => continue,
+            '0'..='9' => {
+                let mut n = c.to_digit(10).unwrap() as i64;
+                while let Some(d) = chars.peek().and_then(|(_, c)| c.to_digit(10)) {
+                    n = n
+                        .checked_mul(10)
+                        .and_then(|n| n.checked_add(d as i64))
+                        .ok_or(ExprError::LiteralTooLarge { col })?;
+                    chars.next();
+                }
+                Lexeme::Num(n)
+            }
+            // `$n` refers to the n-th result of the REPL
+            '$' if chars.peek().is_some_and(|(_, c)| c.is_ascii_digit()) => {
+                let mut name = String::from('$');
+                while let Some((_, d)) = chars.next_if(|(_, c)| c.is_ascii_digit()) {
+                    name.push(d);
+                }
+                Lexeme::Var(name)
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                let mut name = String::from(c);
+                while let Some((_, c)) = chars.next_if(|(_, c)| c.is_alphanumeric() || *c == '_') {
+                    name.push(c);
+                }
+                match name.as_str() {
+                    "let" => Lexeme::Let,
+                    _ => Lexeme::Var(name),
+                }
+            }
+            '=' => Lexeme::Assign,
+            '(' => Lexeme::Open,
+            ')' => Lexeme::Close,
+            '+' | '-' | '*' | '/' | '%' | '^' => Lexeme::Op(c),
+            c => return Err(ExprError::UnexpectedChar { col, c }),
+        };
+        lexemes.push((col, lexeme));
+    }
+    Ok(lexemes)
+}
+
+/// Parses `input` with a Pratt parser driven by the `precedence` table.
+#[cfg(test)]
+pub fn parse(input: &str, precedence: &Precedence) -> Result<Expr, ExprError> {
+    let mut parser = Parser::new(input, precedence)?;
+    let expr = parser.expr(i64::MIN)?;
+    parser.finish(expr)
+}
+
+/// Parses either a `let name = expr` binding or a plain expression.
+pub fn parse_stmt(input: &str, precedence: &Precedence) -> Result<Stmt, ExprError> {
+    let mut parser = Parser::new(input, precedence)?;
+    if parser.lexemes.next_if(|(_, l)| *l == Lexeme::Let).is_none() {
+        let expr = parser.expr(i64::MIN)?;
+        return parser.finish(expr).map(Stmt::Expr);
+    }
+
+    let name = match parser.lexemes.next() {
+        Some((_, Lexeme::Var(name))) if !name.starts_with('$') => name,
+        Some((col, _)) => return Err(ExprError::ExpectedName { col }),
+        None => return Err(ExprError::ExpectedName { col: parser.end }),
+    };
+    match parser.lexemes.next() {
+        Some((_, Lexeme::Assign)) => {}
+        Some((col, _)) => return Err(ExprError::ExpectedAssign { col }),
+        None => return Err(ExprError::ExpectedAssign { col: parser.end }),
+    }
+    let expr = parser.expr(i64::MIN)?;
+    parser.finish(expr).map(|expr| Stmt::Let(name, expr))
+}
+
+struct Parser<'a> {
+    lexemes: Peekable<IntoIter<(usize, Lexeme)>>,
+    precedence: &'a Precedence,
+    /// number of currently open parentheses
+    depth: usize,
+    /// column just past the end of the input
+    end: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn new(input: &str, precedence: &'a Precedence) -> Result<Self, ExprError> {
+        Ok(Self {
+            lexemes: lex(input)?.into_iter().peekable(),
+            precedence,
+            depth: 0,
+            end: input.chars().count() + 1,
+        })
+    }
+
+    /// Checks that `expr` consumed the whole input.
+    fn finish(mut self, expr: Expr) -> Result<Expr, ExprError> {
+        match self.lexemes.next() {
+            None => Ok(expr),
+            Some((col, Lexeme::Close)) => Err(ExprError::UnbalancedParens { col }),
+            Some((col, Lexeme::Assign)) => Err(ExprError::UnexpectedChar { col, c: '=' }),
+            Some((col, _)) => Err(ExprError::TrailingOperand { col }),
+        }
+    }
+
+    /// Parses an expression whose binary operators all bind at least as tight as `min_prec`,
+    /// which is wider than the precedences so that `prec + 1` can't overflow.
+    fn expr(&mut self, min_prec: i64) -> Result<Expr, ExprError> {
+        let mut lhs = self.operand()?;
+        while let Some(&(col, Lexeme::Op(op))) = self.lexemes.peek() {
+            let (prec, assoc) = *self
+                .precedence
+                .get(&op)
+                .ok_or(ExprError::UnknownOperator { col, op })?;
+            let prec = i64::from(prec);
+            if prec < min_prec {
+                break;
+            }
+            self.lexemes.next();
+            let next_min = match assoc {
+                Assoc::Left => prec + 1,
+                Assoc::Right => prec,
+            };
+            let rhs = self.expr(next_min)?;
+            lhs = Expr::BinOp(op, Box::new(lhs), Box::new(rhs));
+        }
+        Ok(lhs)
+    }
+
+    fn operand(&mut self) -> Result<Expr, ExprError> {
+        match self.lexemes.next() {
+            Some((_, Lexeme::Num(n))) => Ok(Expr::Num(n)),
+            Some((col, Lexeme::Var(name))) => Ok(Expr::Var(name, col)),
+            // unary minus binds tighter than any binary operator
+            Some((_, Lexeme::Op('-'))) => Ok(Expr::Neg(Box::new(self.operand()?))),
+            Some((open, Lexeme::Open)) => {
+                self.depth += 1;
+                let inner = self.expr(i64::MIN)?;
+                self.depth -= 1;
+                match self.lexemes.next() {
+                    Some((_, Lexeme::Close)) => Ok(Expr::Group(Box::new(inner))),
+                    Some((col, _)) => Err(ExprError::TrailingOperand { col }),
+                    None => Err(ExprError::UnbalancedParens { col: open }),
+                }
+            }
+            Some((col, Lexeme::Close)) if self.depth == 0 => {
+                Err(ExprError::UnbalancedParens { col })
+            }
+            Some((col, _)) => Err(ExprError::MissingOperand { col }),
+            None => Err(ExprError::MissingOperand { col: self.end }),
+        }
+    }
+}
+
+impl Expr {
+    pub fn eval<N: Number>(&self, vars: &Vars<N>) -> Result<N, ExprError> {
+        match self {
+            Expr::Num(n) => Ok(N::from_i64(*n)),
+            Expr::BinOp(op, lhs, rhs) => N::apply(*op, lhs.eval(vars)?, rhs.eval(vars)?),
+            Expr::Neg(x) => x.eval(vars)?.neg(),
+            Expr::Var(name, col) => {
+                vars.get(name)
+                    .cloned()
+                    .ok_or_else(|| ExprError::UnknownVariable {
+                        col: *col,
+                        name: name.clone(),
+                    })
+            }
+            Expr::Group(x) => x.eval(vars),
+        }
+    }
+
+    pub fn to_postfix(&self) -> Vec<Token> {
+        let mut postfix = Vec::new();
+        self.push_postfix(&mut postfix);
+        postfix
+    }
+
+    fn push_postfix(&self, postfix: &mut Vec<Token>) {
+        match self {
+            Expr::Num(n) => postfix.push(Token::Num(*n)),
+            Expr::BinOp(op, lhs, rhs) => {
+                lhs.push_postfix(postfix);
+                rhs.push_postfix(postfix);
+                postfix
+                    .push(Token::try_from(*op).expect("the parser only accepts known operators"));
+            }
+            Expr::Neg(x) => {
+                x.push_postfix(postfix);
+                postfix.push(Token::Neg);
+            }
+            Expr::Var(name, col) => postfix.push(Token::Var(name.clone(), *col)),
+            Expr::Group(x) => x.push_postfix(postfix),
+        }
+    }
+
+    /// Prints the expression with only the parentheses that `precedence` requires.
+    pub fn pretty(&self, precedence: &Precedence) -> String {
+        match self {
+            Expr::Num(n) => n.to_string(),
+            Expr::BinOp(op, lhs, rhs) => {
+                let (prec, assoc) = precedence[op];
+                let side = |x: &Expr, parens_on_tie: bool| {
+                    let needs_parens = match x.binding(precedence) {
+                        Some(p) => p < prec || (p == prec && parens_on_tie),
+                        None => false,
+                    };
+                    let s = x.pretty(precedence);
+                    if needs_parens {
+                        format!("({s})")
+                    } else {
+                        s
+                    }
+                };
+                let lhs = side(lhs, assoc == Assoc::Right);
+                let rhs = side(rhs, assoc == Assoc::Left);
+                format!("{lhs} {op} {rhs}")
+            }
+            Expr::Neg(x) => match x.binding(precedence) {
+                Some(_) => format!("-({})", x.pretty(precedence)),
+                None => format!("-{}", x.pretty(precedence)),
+            },
+            Expr::Var(name, _) => name.clone(),
+            Expr::Group(x) => x.pretty(precedence),
+        }
+    }
+
+    /// Prints the tree as an s-expression, e.g. `(+ 1 (* 2 3))`.
+    pub fn tree(&self) -> String {
+        match self {
+            Expr::Num(n) => n.to_string(),
+            Expr::BinOp(op, lhs, rhs) => format!("({op} {} {})", lhs.tree(), rhs.tree()),
+            Expr::Neg(x) => format!("(- {})", x.tree()),
+            Expr::Var(name, _) => name.clone(),
+            Expr::Group(x) => x.tree(),
+        }
+    }
+
+    /// Precedence of the outermost binary operator, ignoring groups.
+    fn binding(&self, precedence: &Precedence) -> Option<i32> {
+        match self {
+            Expr::BinOp(op, _, _) => Some(precedence[op].0),
+            Expr::Group(x) => x.binding(precedence),
+            Expr::Num(_) | Expr::Neg(_) | Expr::Var(..) => None,
+        }
+    }
+}
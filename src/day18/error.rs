@@ -0,0 +1,61 @@
+use thiserror::Error;
+
+/// Everything that can go wrong while parsing or evaluating an expression. Columns are
+/// 1-based character positions in the input line.
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum ExprError {
+    #[error("unexpected character `{c}`")]
+    UnexpectedChar { col: usize, c: char },
+    #[error("unbalanced parenthesis")]
+    UnbalancedParens { col: usize },
+    #[error("missing operand")]
+    MissingOperand { col: usize },
+    #[error("operand without an operator before it")]
+    TrailingOperand { col: usize },
+    #[error("the operator `{op}` is missing from the precedence table")]
+    UnknownOperator { col: usize, op: char },
+    #[error("number literal is too large")]
+    LiteralTooLarge { col: usize },
+    #[error("division by zero")]
+    DivisionByZero,
+    #[error("invalid exponent: {0}")]
+    InvalidExponent(String),
+    #[error("arithmetic overflow")]
+    Overflow,
+    #[error("`{name}` is not defined")]
+    UnknownVariable { col: usize, name: String },
+    #[error("expected a variable name")]
+    ExpectedName { col: usize },
+    #[error("expected `=`")]
+    ExpectedAssign { col: usize },
+    #[error("the postfix program doesn't leave exactly one value on the stack")]
+    InvalidPostfix,
+}
+
+impl ExprError {
+    pub fn col(&self) -> Option<usize> {
+        match *self {
+            ExprError::UnexpectedChar { col, .. }
+            | ExprError::UnbalancedParens { col }
+            | ExprError::MissingOperand { col }
+            | ExprError::TrailingOperand { col }
+            | ExprError::UnknownOperator { col, .. }
+            | ExprError::LiteralTooLarge { col }
+            | ExprError::UnknownVariable { col, .. }
+            | ExprError::ExpectedName { col }
+            | ExprError::ExpectedAssign { col } => Some(col),
+            ExprError::DivisionByZero
+            | ExprError::InvalidExponent(_)
+            | ExprError::Overflow
+            | ExprError::InvalidPostfix => None,
+        }
+    }
+
+    /// Renders the error below the offending `input`, with a caret pointing at the column.
+    pub fn render(&self, input: &str) -> String {
+        match self.col() {
+            Some(col) => format!("{self}\n    {input}\n    {:>col$}", "^"),
+            None => format!("{self}\n    {input}"),
+        }
+    }
+}
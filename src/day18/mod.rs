@@ -0,0 +1,1009 @@
+use crate::Solution;
+use anyhow::{anyhow, bail};
+use rayon::prelude::*;
+use std::{collections::HashMap, env::args, fmt, fs, num::Wrapping, time::Instant};
+
+mod ast;
+mod error;
+mod num;
+mod repl;
+mod simplify;
+mod vm;
+
+use ast::Stmt;
+use error::ExprError;
+use num::{BigInt, Number};
+use simplify::Equivalence;
+use vm::Program;
+
+const USAGE: &str = "\
+usage: day18 <input> [--profile <profile>] [--numbers <numbers>] [--ast] [--parallel]
+       day18 <input> --bench [--profile <profile>]
+       day18 <input> --diff [--profile <profile>]
+       day18 --repl [--profile <profile>] [--numbers <numbers>]
+
+every line of <input> is an expression or a `let <name> = <expr>` binding,
+the sum is taken over the expression lines
+
+profiles: left-to-right, addition-first, standard, custom=<ops>
+  where <ops> is a comma separated list of an operator, its precedence from
+  0 to 1000 and optionally `r` for right associativity, e.g. `custom=+2,-2,*1,^3r`
+numbers: checked (default, fails on overflow), wrapping, big (up to 2^65536)";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Numbers {
+    Checked,
+    Wrapping,
+    Big,
+}
+
+/// Entry point of the `day18` binary.
+pub fn main() -> anyhow::Result<()> {
+    let mut args = args().skip(1);
+    let mut path = None;
+    let mut profile = None;
+    let mut numbers = Numbers::Checked;
+    let mut show_ast = false;
+    let mut interactive = false;
+    let mut bench = false;
+    let mut parallel = false;
+    let mut diff = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--profile" => {
+                let name = args
+                    .next()
+                    .ok_or_else(|| anyhow!("`--profile` expects a value\n{USAGE}"))?;
+                profile = Some(parse_profile(&name).map_err(|e| anyhow!("{e}\n{USAGE}"))?);
+            }
+            "--numbers" => {
+                numbers = match args.next().as_deref() {
+                    Some("checked") => Numbers::Checked,
+                    Some("wrapping") => Numbers::Wrapping,
+                    Some("big") => Numbers::Big,
+                    _ => bail!("`--numbers` expects checked, wrapping or big\n{USAGE}"),
+                }
+            }
+            "--ast" => show_ast = true,
+            "--repl" => interactive = true,
+            "--bench" => bench = true,
+            "--parallel" => parallel = true,
+            "--diff" => diff = true,
+            _ if path.is_none() => path = Some(arg),
+            x => bail!("unexpected argument: `{x}`\n{USAGE}"),
+        }
+    }
+    if interactive {
+        let precedence = profile.unwrap_or_else(addition_first);
+        return match numbers {
+            Numbers::Checked => repl::run::<i64>(precedence),
+            Numbers::Wrapping => repl::run::<Wrapping<i64>>(precedence),
+            Numbers::Big => repl::run::<BigInt>(precedence),
+        };
+    }
+    let Some(path) = path else {
+        bail!("please provide the path to input file\n{USAGE}")
+    };
+    let lines = Day18::parse(&fs::read_to_string(path)?)?;
+    if bench {
+        return run_bench(&lines, &profile.unwrap_or_else(addition_first));
+    }
+    if diff {
+        return run_diff(&lines, &profile.unwrap_or_else(addition_first));
+    }
+
+    match numbers {
+        Numbers::Checked => report::<i64>(&lines, profile, show_ast, parallel),
+        Numbers::Wrapping => report::<Wrapping<i64>>(&lines, profile, show_ast, parallel),
+        Numbers::Big => report::<BigInt>(&lines, profile, show_ast, parallel),
+    }
+}
+
+/// Prints either every line with its value or the sums, evaluated with numbers of type `N`.
+fn report<N: Number>(
+    lines: &[String],
+    profile: Option<Precedence>,
+    show_ast: bool,
+    parallel: bool,
+) -> anyhow::Result<()> {
+    if show_ast {
+        let precedence = profile.unwrap_or_else(addition_first);
+        let mut vars: Vars<N> = Vars::new();
+        for (n, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let out = ast::parse_stmt(line, &precedence)
+                .and_then(|stmt| match stmt {
+                    Stmt::Let(name, expr) => {
+                        let value = expr.eval(&vars)?;
+                        let out = format!("let {name} = {} = {value}", expr.pretty(&precedence));
+                        vars.insert(name, value);
+                        Ok(out)
+                    }
+                    Stmt::Expr(expr) => Ok(format!(
+                        "{} = {}",
+                        expr.pretty(&precedence),
+                        expr.eval(&vars)?
+                    )),
+                })
+                .map_err(|e| anyhow!("line {}: {}", n + 1, e.render(line)))?;
+            println!("{out}");
+        }
+        return Ok(());
+    }
+
+    let sum_lines: fn(&[String], &Precedence) -> anyhow::Result<N> = if parallel {
+        sum_lines_parallel
+    } else {
+        sum_lines
+    };
+    match profile {
+        Some(precedence) => {
+            let sum = sum_lines(lines, &precedence)?;
+            println!("The sum is: {sum}");
+        }
+        None => {
+            let part1 = sum_lines(lines, &left_to_right())?;
+            let part2 = sum_lines(lines, &addition_first())?;
+            println!("The sum is: {part1} (left-to-right)");
+            println!("The sum is: {part2} (addition-first)");
+        }
+    }
+    Ok(())
+}
+
+/// Compares the bytecode VM against the postfix interpreter, with checked `i64` numbers.
+fn run_bench(lines: &[String], precedence: &Precedence) -> anyhow::Result<()> {
+    const ROUNDS: u32 = 100;
+
+    let start = Instant::now();
+    let mut postfix = Vec::new();
+    for (n, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let stmt = ast::parse_stmt(line, precedence).map_err(at_line(lines, n))?;
+        postfix.push(match stmt {
+            Stmt::Let(name, expr) => (n, Some(name), expr.to_postfix()),
+            Stmt::Expr(expr) => (n, None, expr.to_postfix()),
+        });
+    }
+    println!("Parsed {} lines in {:.2?}", postfix.len(), start.elapsed());
+
+    let start = Instant::now();
+    let programs = postfix
+        .iter()
+        .map(|(n, name, tokens)| {
+            let program = Program::compile(tokens).map_err(at_line(lines, *n))?;
+            Ok((*n, name.clone(), program))
+        })
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    let bytes: usize = programs.iter().map(|(_, _, p)| p.code_len()).sum();
+    println!(
+        "Compiled to {bytes} bytes of bytecode in {:.2?}",
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let mut postfix_sum = 0;
+    for _ in 0..ROUNDS {
+        postfix_sum = run_statements(lines, &postfix, |tokens, vars| eval_postfix(tokens, vars))?;
+    }
+    let postfix_time = start.elapsed() / ROUNDS;
+
+    let start = Instant::now();
+    let mut vm_sum = 0;
+    for _ in 0..ROUNDS {
+        vm_sum = run_statements(lines, &programs, Program::run)?;
+    }
+    let vm_time = start.elapsed() / ROUNDS;
+
+    if postfix_sum != vm_sum {
+        bail!("the VM computed {vm_sum} but the postfix interpreter {postfix_sum}");
+    }
+    println!("Postfix:  {postfix_sum} in {postfix_time:.2?} per round");
+    println!("Bytecode: {vm_sum} in {vm_time:.2?} per round");
+    Ok(())
+}
+
+/// Prints the lines whose value may differ between the left-to-right rules and `precedence`.
+fn run_diff(lines: &[String], precedence: &Precedence) -> anyhow::Result<()> {
+    let base = left_to_right();
+    let (mut same, mut different, mut unknown) = (0, 0, 0);
+    for (n, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let compared = simplify::compare(line, &base, precedence).map_err(at_line(lines, n))?;
+        let counter = match compared {
+            Equivalence::Same => {
+                same += 1;
+                continue;
+            }
+            Equivalence::Different => &mut different,
+            Equivalence::Unknown => &mut unknown,
+        };
+        *counter += 1;
+
+        let simple = |p| match ast::parse_stmt(line, p) {
+            Ok(Stmt::Let(_, expr) | Stmt::Expr(expr)) => simplify::simplify(&expr).to_string(),
+            Err(_) => unreachable!("the line was parsed by `compare`"),
+        };
+        println!("line {}: {line}", n + 1);
+        println!("    {}", simple(&base));
+        println!("    {}", simple(precedence));
+    }
+    println!("{same} lines are the same, {different} differ, {unknown} may differ");
+    Ok(())
+}
+
+/// Sums the values of already parsed statements of `lines`, with `eval` evaluating each
+/// statement.
+fn run_statements<T>(
+    lines: &[String],
+    statements: &[(usize, Option<String>, T)],
+    eval: impl Fn(&T, &Vars<i64>) -> Result<i64, ExprError>,
+) -> anyhow::Result<i64> {
+    let mut vars = Vars::new();
+    let mut sum = 0i64;
+    for (n, name, code) in statements {
+        let value = eval(code, &vars).map_err(at_line(lines, *n))?;
+        match name {
+            Some(name) => {
+                vars.insert(name.clone(), value);
+            }
+            None => sum = Number::apply('+', sum, value).map_err(at_line(lines, *n))?,
+        }
+    }
+    Ok(sum)
+}
+
+/// Reports an error of the `n`-th line (0-based) with its line number and position.
+fn at_line(lines: &[String], n: usize) -> impl Fn(ExprError) -> anyhow::Error + '_ {
+    move |e| anyhow!("line {}: {}", n + 1, e.render(&lines[n]))
+}
+
+pub struct Day18;
+
+impl Solution for Day18 {
+    type Input = Vec<String>;
+    type Output = i64;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        Ok(input.lines().map(String::from).collect())
+    }
+
+    fn part1(lines: &Self::Input) -> anyhow::Result<i64> {
+        sum_lines(lines, &left_to_right())
+    }
+
+    fn part2(lines: &Self::Input) -> anyhow::Result<i64> {
+        sum_lines(lines, &addition_first())
+    }
+}
+
+/// Runs the lines as a program and sums the values of its expression lines, reporting the
+/// first line that fails to evaluate or makes the sum overflow. Blank lines are skipped.
+fn sum_lines<N: Number>(lines: &[String], precedence: &Precedence) -> anyhow::Result<N> {
+    let mut vars = Vars::new();
+    let mut sum = N::from_i64(0);
+    for (n, line) in lines.iter().enumerate() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        sum = exec_line(line, precedence, &mut vars)
+            .and_then(|value| match value {
+                Some(value) => N::apply('+', sum, value),
+                None => Ok(sum),
+            })
+            .map_err(|e| anyhow!("line {}: {}", n + 1, e.render(line)))?;
+    }
+    Ok(sum)
+}
+
+/// Same as [`sum_lines`], but parses and evaluates the lines in parallel.
+///
+/// Only the `let` bindings are evaluated in order. Every binding is stored once, along with
+/// its line, and a line sees the latest binding of a name before it. The first failing line
+/// is reported, just like in the sequential version.
+fn sum_lines_parallel<N: Number>(lines: &[String], precedence: &Precedence) -> anyhow::Result<N> {
+    let stmts: Vec<Result<Option<Stmt>, ExprError>> = lines
+        .par_iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                Ok(None)
+            } else {
+                ast::parse_stmt(line, precedence).map(Some)
+            }
+        })
+        .collect();
+
+    // the values bound to every name, ordered by the line binding them
+    let mut bindings: HashMap<&str, Vec<(usize, N)>> = HashMap::new();
+    let mut failure = None;
+    for (n, stmt) in stmts.iter().enumerate() {
+        let result = match stmt {
+            Ok(Some(Stmt::Let(name, expr))) => {
+                let value = eval_postfix_with(&expr.to_postfix(), |var| binding(&bindings, var, n));
+                value.map(|value| bindings.entry(name).or_default().push((n, value)))
+            }
+            Ok(_) => Ok(()),
+            Err(e) => Err(e.clone()),
+        };
+        if let Err(e) = result {
+            failure = Some((n, e));
+            break;
+        }
+    }
+
+    let end = failure.as_ref().map_or(lines.len(), |(n, _)| *n);
+    let values: Vec<Result<Option<N>, ExprError>> = stmts[..end]
+        .par_iter()
+        .enumerate()
+        .map(|(n, stmt)| match stmt {
+            Ok(Some(Stmt::Expr(expr))) => {
+                eval_postfix_with(&expr.to_postfix(), |var| binding(&bindings, var, n)).map(Some)
+            }
+            _ => Ok(None),
+        })
+        .collect();
+
+    let mut sum = N::from_i64(0);
+    for ((n, line), value) in lines.iter().enumerate().zip(values) {
+        sum = value
+            .and_then(|value| match value {
+                Some(value) => N::apply('+', sum, value),
+                None => Ok(sum),
+            })
+            .map_err(|e| anyhow!("line {}: {}", n + 1, e.render(line)))?;
+    }
+    match failure {
+        Some((n, e)) => Err(anyhow!("line {}: {}", n + 1, e.render(&lines[n]))),
+        None => Ok(sum),
+    }
+}
+
+/// Looks up the latest binding of `name` before `line` in the bindings of
+/// [`sum_lines_parallel`].
+fn binding<N: Clone>(
+    bindings: &HashMap<&str, Vec<(usize, N)>>,
+    name: &str,
+    line: usize,
+) -> Option<N> {
+    let lines = bindings.get(name)?;
+    let i = lines.partition_point(|(n, _)| *n < line);
+    i.checked_sub(1).map(|i| lines[i].1.clone())
+}
+
+/// Runs one line of a program: a `let` binds its value in `vars`, an expression returns it.
+fn exec_line<N: Number>(
+    input: &str,
+    precedence: &Precedence,
+    vars: &mut Vars<N>,
+) -> Result<Option<N>, ExprError> {
+    match ast::parse_stmt(input, precedence)? {
+        Stmt::Let(name, expr) => {
+            let value = eval_postfix(&expr.to_postfix(), vars)?;
+            vars.insert(name, value);
+            Ok(None)
+        }
+        Stmt::Expr(expr) => eval_postfix(&expr.to_postfix(), vars).map(Some),
+    }
+}
+
+#[cfg(test)]
+fn eval_line<N: Number>(input: &str, precedence: &Precedence) -> Result<N, ExprError> {
+    eval_postfix(&ast::parse(input, precedence)?.to_postfix(), &Vars::new())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Assoc {
+    Left,
+    Right,
+}
+
+/// Binding power and associativity of every binary operator, higher binds tighter.
+/// Unary minus always binds tighter than any binary operator.
+type Precedence = HashMap<char, (i32, Assoc)>;
+
+/// Values of the variables an expression may refer to.
+type Vars<N> = HashMap<String, N>;
+
+/// Part 1 rules: every operator has the same precedence, except for `^`.
+fn left_to_right() -> Precedence {
+    use Assoc::*;
+    HashMap::from([
+        ('+', (1, Left)),
+        ('-', (1, Left)),
+        ('*', (1, Left)),
+        ('/', (1, Left)),
+        ('%', (1, Left)),
+        ('^', (2, Right)),
+    ])
+}
+
+/// Part 2 rules: addition and subtraction bind tighter than multiplication and division.
+fn addition_first() -> Precedence {
+    use Assoc::*;
+    HashMap::from([
+        ('+', (2, Left)),
+        ('-', (2, Left)),
+        ('*', (1, Left)),
+        ('/', (1, Left)),
+        ('%', (1, Left)),
+        ('^', (3, Right)),
+    ])
+}
+
+/// The usual arithmetic rules: `^` before `*`, `/` and `%`, before `+` and `-`.
+fn standard() -> Precedence {
+    use Assoc::*;
+    HashMap::from([
+        ('+', (1, Left)),
+        ('-', (1, Left)),
+        ('*', (2, Left)),
+        ('/', (2, Left)),
+        ('%', (2, Left)),
+        ('^', (3, Right)),
+    ])
+}
+
+/// Looks up a named profile or parses a `custom=` one, see [`USAGE`].
+fn parse_profile(name: &str) -> Result<Precedence, String> {
+    match name {
+        "left-to-right" => return Ok(left_to_right()),
+        "addition-first" => return Ok(addition_first()),
+        "standard" => return Ok(standard()),
+        _ => {}
+    }
+    let Some(ops) = name.strip_prefix("custom=") else {
+        return Err(format!(
+            "unknown profile: `{name}`, \
+             expected left-to-right, addition-first, standard or custom=<ops>"
+        ));
+    };
+
+    let mut precedence = Precedence::new();
+    for entry in ops.split(',') {
+        let mut chars = entry.chars();
+        let op = chars.next().ok_or("empty operator entry in the profile")?;
+        if !is_binary_operator(op) {
+            return Err(format!("unknown operator in the profile: `{op}`"));
+        }
+        let rest = chars.as_str();
+        let (prec, assoc) = match rest.strip_suffix('r') {
+            Some(prec) => (prec, Assoc::Right),
+            None => (rest, Assoc::Left),
+        };
+        let prec = parse_precedence(prec)
+            .ok_or_else(|| format!("invalid precedence for `{op}`: `{rest}`"))?;
+        precedence.insert(op, (prec, assoc));
+    }
+    Ok(precedence)
+}
+
+/// Parses a precedence, which must be in `0..=1000`.
+fn parse_precedence(s: &str) -> Option<i32> {
+    s.parse().ok().filter(|prec| (0..=1000).contains(prec))
+}
+
+fn is_binary_operator(op: char) -> bool {
+    Token::try_from(op).is_ok_and(|t| t != Token::Neg)
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Num(i64),
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+    Pow,
+    Neg,
+    /// A variable and the column it's referenced at.
+    Var(String, usize),
+}
+
+fn eval_postfix<N: Number>(tokens: &[Token], vars: &Vars<N>) -> Result<N, ExprError> {
+    eval_postfix_with(tokens, |name| vars.get(name).cloned())
+}
+
+/// Like [`eval_postfix`], but looks the variables up with `lookup`.
+fn eval_postfix_with<N: Number>(
+    tokens: &[Token],
+    lookup: impl Fn(&str) -> Option<N>,
+) -> Result<N, ExprError> {
+    let mut stack: Vec<N> = Vec::new();
+    for t in tokens {
+        match t {
+            Token::Num(n) => stack.push(N::from_i64(*n)),
+            Token::Var(name, col) => match lookup(name) {
+                Some(value) => stack.push(value),
+                None => {
+                    return Err(ExprError::UnknownVariable {
+                        col: *col,
+                        name: name.clone(),
+                    })
+                }
+            },
+            Token::Neg => {
+                let x = stack.pop().ok_or(ExprError::InvalidPostfix)?;
+                stack.push(x.neg()?);
+            }
+            op => {
+                let rhs = stack.pop().ok_or(ExprError::InvalidPostfix)?;
+                let lhs = stack.pop().ok_or(ExprError::InvalidPostfix)?;
+                stack.push(N::apply(op.symbol(), lhs, rhs)?);
+            }
+        }
+    }
+
+    match stack.len() {
+        1 => Ok(stack.pop().unwrap()),
+        _ => Err(ExprError::InvalidPostfix),
+    }
+}
+
+impl Token {
+    fn symbol(&self) -> char {
+        match self {
+            Token::Num(_) => '#',
+            Token::Add => '+',
+            Token::Sub => '-',
+            Token::Mul => '*',
+            Token::Div => '/',
+            Token::Rem => '%',
+            Token::Pow => '^',
+            Token::Neg => '~',
+            Token::Var(..) => '$',
+        }
+    }
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Num(n) => write!(f, "{n}"),
+            Token::Var(name, _) => write!(f, "{name}"),
+            op => write!(f, "{}", op.symbol()),
+        }
+    }
+}
+
+impl TryFrom<char> for Token {
+    type Error = String;
+
+    fn try_from(value: char) -> Result<Self, Self::Error> {
+        match value {
+            '+' => Ok(Self::Add),
+            '-' => Ok(Self::Sub),
+            '*' => Ok(Self::Mul),
+            '/' => Ok(Self::Div),
+            '%' => Ok(Self::Rem),
+            '^' => Ok(Self::Pow),
+            '~' => Ok(Self::Neg),
+            x => Err(format!("invalid token: {x}")),
+        }
+    }
+}
+
+#[cfg(test)]
+fn eval(input: &str, precedence: &Precedence) -> Result<i64, ExprError> {
+    eval_line(input, precedence)
+}
+
+#[test]
+fn numeric_backends() {
+    let p = standard();
+    let big = "99999999999 * 99999999999 * 99999999999 - 1";
+    assert_eq!(eval(big, &p), Err(ExprError::Overflow));
+    assert_eq!(
+        eval_line::<BigInt>(big, &p).unwrap().to_string(),
+        "999999999970000000000299999999998"
+    );
+    assert_eq!(
+        eval_line::<Wrapping<i64>>(big, &p),
+        Ok(Wrapping(99999999999i64.wrapping_pow(3) - 1))
+    );
+    assert_eq!(
+        eval_line::<BigInt>("-(2 ^ 100) / 3 ^ 20", &p)
+            .unwrap()
+            .to_string(),
+        "-363558641556578823726"
+    );
+    assert_eq!(
+        eval_line::<BigInt>("-(2 ^ 100) % 3 ^ 20", &p)
+            .unwrap()
+            .to_string(),
+        "-1957707250"
+    );
+    assert_eq!(
+        eval_line::<BigInt>("(2 ^ 64 + 7) / (2 ^ 40 + 3)", &p)
+            .unwrap()
+            .to_string(),
+        "16777215"
+    );
+    assert_eq!(eval_line::<BigInt>("0 - 0", &p).unwrap().to_string(), "0");
+    assert_eq!(
+        eval_line::<BigInt>("2 ^ -1", &p),
+        Err(ExprError::InvalidExponent("-1".to_owned()))
+    );
+    assert_eq!(
+        eval_line::<BigInt>("9 ^ 4000000000", &p),
+        Err(ExprError::Overflow)
+    );
+    assert_eq!(
+        eval_line::<BigInt>("2 ^ 65535", &p).unwrap(),
+        eval_line::<BigInt>("2 ^ 65534 * 2", &p).unwrap()
+    );
+    assert_eq!(
+        eval_line::<BigInt>("2 ^ 65536", &p),
+        Err(ExprError::Overflow)
+    );
+    assert_eq!(
+        eval_line::<BigInt>("-1 ^ 4000000001", &p)
+            .unwrap()
+            .to_string(),
+        "-1"
+    );
+}
+
+#[test]
+fn precedence_profiles() {
+    let example = "1 + (2 * 3) + (4 * (5 + 6))";
+    assert_eq!(eval(example, &left_to_right()), Ok(51));
+    assert_eq!(eval(example, &addition_first()), Ok(51));
+    let example = "2 * 3 + (4 * 5)";
+    assert_eq!(eval(example, &left_to_right()), Ok(26));
+    assert_eq!(eval(example, &addition_first()), Ok(46));
+    let example = "((2 + 4 * 9) * (6 + 9 * 8 + 6) + 6) + 2 + 4 * 2";
+    assert_eq!(eval(example, &left_to_right()), Ok(13632));
+    assert_eq!(eval(example, &addition_first()), Ok(23340));
+}
+
+#[test]
+fn extended_operators() {
+    let p = addition_first();
+    assert_eq!(eval("12 * 10 - 3", &p), Ok(84));
+    assert_eq!(eval("2 ^ 3 ^ 2", &p), Ok(512));
+    assert_eq!(eval("-3 + 5", &p), Ok(2));
+    assert_eq!(eval("-(2 + 3) * --2", &p), Ok(-10));
+    assert_eq!(eval("17 % 5 / 2", &p), Ok(1));
+    assert_eq!(eval("1 / 0", &p), Err(ExprError::DivisionByZero));
+}
+
+#[test]
+fn custom_profile() {
+    let p = parse_profile("custom=+1,*2,^3r").unwrap();
+    assert_eq!(
+        p,
+        HashMap::from([
+            ('+', (1, Assoc::Left)),
+            ('*', (2, Assoc::Left)),
+            ('^', (3, Assoc::Right))
+        ])
+    );
+    assert_eq!(eval("1 + 2 * 3", &p), Ok(7));
+    assert!(eval("1 - 2", &p).is_err());
+    assert_eq!(parse_profile("standard").unwrap(), standard());
+    assert!(parse_profile("custom=+x").is_err());
+    assert!(parse_profile("custom=+1000,*0").is_ok());
+    for prec in ["1001", "-1", "2147483647", "-2147483648", "99999999999"] {
+        let err = parse_profile(&format!("custom=+{prec}")).unwrap_err();
+        assert_eq!(err, format!("invalid precedence for `+`: `{prec}`"));
+    }
+    assert!(parse_profile("custom=a1").is_err());
+    assert_eq!(
+        parse_profile("fancy").unwrap_err(),
+        "unknown profile: `fancy`, expected left-to-right, addition-first, standard or custom=<ops>"
+    );
+}
+
+#[test]
+fn ast() {
+    use ast::Expr;
+
+    let p = standard();
+    let expr = ast::parse("(1 + 2) * -3", &p).unwrap();
+    assert_eq!(
+        expr,
+        Expr::BinOp(
+            '*',
+            Box::new(Expr::Group(Box::new(Expr::BinOp(
+                '+',
+                Box::new(Expr::Num(1)),
+                Box::new(Expr::Num(2))
+            )))),
+            Box::new(Expr::Neg(Box::new(Expr::Num(3))))
+        )
+    );
+    assert_eq!(expr.eval::<i64>(&Vars::new()), Ok(-9));
+    assert_eq!(
+        expr.to_postfix(),
+        vec![
+            Token::Num(1),
+            Token::Num(2),
+            Token::Add,
+            Token::Num(3),
+            Token::Neg,
+            Token::Mul
+        ]
+    );
+
+    let pretty = |input: &str, p: &Precedence| ast::parse(input, p).unwrap().pretty(p);
+    assert_eq!(pretty("(1 + 2) * -3", &p), "(1 + 2) * -3");
+    assert_eq!(pretty("((1 * 2)) + (3)", &p), "1 * 2 + 3");
+    assert_eq!(pretty("1 - (2 - 3) - (4 + 5)", &p), "1 - (2 - 3) - (4 + 5)");
+    assert_eq!(pretty("(2 ^ 3) ^ (4 ^ 5)", &p), "(2 ^ 3) ^ 4 ^ 5");
+    assert_eq!(pretty("-(2 * 3)", &p), "-(2 * 3)");
+    assert_eq!(pretty("(1 + 2) * 3", &addition_first()), "1 + 2 * 3");
+
+    for prec in [i32::MIN, i32::MAX] {
+        let p = HashMap::from([('-', (prec, Assoc::Left))]);
+        assert_eq!(eval("10 - 2 - 3", &p), Ok(5), "{prec}");
+        let p = HashMap::from([('-', (prec, Assoc::Right))]);
+        assert_eq!(eval("10 - 2 - 3", &p), Ok(11), "{prec}");
+    }
+}
+
+#[test]
+fn errors() {
+    let p = standard();
+    assert_eq!(
+        eval("1 + #", &p),
+        Err(ExprError::UnexpectedChar { col: 5, c: '#' })
+    );
+    assert_eq!(eval("1 +", &p), Err(ExprError::MissingOperand { col: 4 }));
+    assert_eq!(
+        eval("1 + * 2", &p),
+        Err(ExprError::MissingOperand { col: 5 })
+    );
+    assert_eq!(eval(")(", &p), Err(ExprError::UnbalancedParens { col: 1 }));
+    assert_eq!(
+        eval("(1 + 2", &p),
+        Err(ExprError::UnbalancedParens { col: 1 })
+    );
+    assert_eq!(
+        eval("1 + 2)", &p),
+        Err(ExprError::UnbalancedParens { col: 6 })
+    );
+    assert_eq!(eval("1 2", &p), Err(ExprError::TrailingOperand { col: 3 }));
+    assert_eq!(
+        eval("(1 2)", &p),
+        Err(ExprError::TrailingOperand { col: 4 })
+    );
+    assert_eq!(eval("()", &p), Err(ExprError::MissingOperand { col: 2 }));
+    assert_eq!(
+        eval_postfix::<i64>(&[Token::Add], &Vars::new()),
+        Err(ExprError::InvalidPostfix)
+    );
+    assert_eq!(
+        eval_postfix::<i64>(&[Token::Num(1), Token::Num(2)], &Vars::new()),
+        Err(ExprError::InvalidPostfix)
+    );
+    assert_eq!(
+        ExprError::MissingOperand { col: 4 }.render("1 +"),
+        "missing operand\n    1 +\n       ^"
+    );
+}
+
+#[test]
+fn repl() {
+    let mut repl = repl::Repl::<i64>::new(left_to_right());
+    assert_eq!(
+        repl.eval("1 + 2 * 3"),
+        Ok(Some(
+            "postfix: 1 2 + 3 *\ntree:    (* (+ 1 2) 3)\n$1 = 9".to_owned()
+        ))
+    );
+    assert!(repl.eval(":prec * 2").is_ok());
+    assert_eq!(
+        repl.eval("$1 - 2 * 3").unwrap().unwrap().lines().last(),
+        Some("$2 = 3")
+    );
+    assert!(repl.eval(":assoc - right").is_ok());
+    assert_eq!(
+        repl.eval("$2 - $1 - 1").unwrap().unwrap().lines().last(),
+        Some("$3 = -5")
+    );
+    assert_eq!(
+        repl.eval("$4"),
+        Err("`$4` is not defined\n    $4\n    ^".to_owned())
+    );
+    assert_eq!(
+        repl.eval("let x = $3 * 2").unwrap().unwrap().lines().last(),
+        Some("x = -10")
+    );
+    assert_eq!(
+        repl.eval("x - 1").unwrap().unwrap().lines().last(),
+        Some("$4 = -11")
+    );
+    assert!(repl.eval(":assoc ~ left").is_err());
+    assert!(repl.eval(":prec + x").is_err());
+    for prec in ["1001", "-1", "2147483647", "-2147483648"] {
+        let err = repl.eval(&format!(":prec + {prec}")).unwrap_err();
+        assert_eq!(err, format!("invalid precedence: `{prec}`"));
+    }
+    assert_eq!(
+        repl.eval(":profile bogus"),
+        Err(
+            "unknown profile: `bogus`, expected left-to-right, addition-first, standard or custom=<ops>"
+                .to_owned()
+        )
+    );
+    assert_eq!(repl.eval(":quit"), Ok(None));
+
+    let mut repl = repl::Repl::<i64>::new(HashMap::from([('*', (2, Assoc::Left))]));
+    assert_eq!(
+        repl.eval(":prec + 1000"),
+        Ok(Some("+ 1000 left\n* 2 left".to_owned()))
+    );
+    assert_eq!(
+        repl.eval(":prec - 0"),
+        Ok(Some("+ 1000 left\n* 2 left\n- 0 left".to_owned()))
+    );
+}
+
+#[test]
+fn programs() {
+    let lines: Vec<String> = [
+        "let width = 2 + 3",
+        "",
+        "let area = width * width",
+        "area + 1",
+        "area",
+    ]
+    .map(String::from)
+    .into();
+    assert_eq!(sum_lines::<i64>(&lines, &standard()).unwrap(), 51);
+    assert_eq!(sum_lines::<i64>(&lines, &left_to_right()).unwrap(), 51);
+
+    let p = standard();
+    let mut vars = Vars::new();
+    assert_eq!(exec_line::<i64>("let x_1 = 4", &p, &mut vars), Ok(None));
+    assert_eq!(exec_line("x_1 ^ 2", &p, &mut vars), Ok(Some(16)));
+    assert_eq!(
+        exec_line("x_1 + y", &p, &mut vars),
+        Err(ExprError::UnknownVariable {
+            col: 7,
+            name: "y".to_owned()
+        })
+    );
+    assert_eq!(
+        exec_line("let 2 = 3", &p, &mut vars),
+        Err(ExprError::ExpectedName { col: 5 })
+    );
+    assert_eq!(
+        exec_line("let x 3", &p, &mut vars),
+        Err(ExprError::ExpectedAssign { col: 7 })
+    );
+    assert_eq!(
+        exec_line("x_1 = 3", &p, &mut vars),
+        Err(ExprError::UnexpectedChar { col: 5, c: '=' })
+    );
+    assert_eq!(
+        exec_line("1 + let", &p, &mut vars),
+        Err(ExprError::MissingOperand { col: 5 })
+    );
+
+    let err = sum_lines::<i64>(&["let a = 1".to_owned(), "a + b".to_owned()], &p).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 2: `b` is not defined\n    a + b\n        ^"
+    );
+}
+
+#[test]
+fn bytecode() {
+    let p = standard();
+    let compile =
+        |input: &str| Program::<i64>::compile(&ast::parse(input, &p).unwrap().to_postfix());
+    let vars = Vars::from([("x".to_owned(), 5), ("y".to_owned(), -2)]);
+
+    // literal-only lines fold into a single `PUSH`
+    assert_eq!(compile("2 * (3 + 4) ^ 2 - -1").unwrap().code_len(), 5);
+    assert_eq!(compile("2 * (3 + 4) ^ 2 - -1").unwrap().run(&vars), Ok(99));
+    assert_eq!(compile("1 / 0").unwrap_err(), ExprError::DivisionByZero);
+
+    for input in [
+        "x * 2 + 3 * 4",
+        "-(x - y) ^ 2",
+        "2 ^ 3 * x - y % 3",
+        "x * x * x",
+        "7 - 2 - x",
+    ] {
+        let postfix = ast::parse(input, &p).unwrap().to_postfix();
+        assert_eq!(
+            compile(input).unwrap().run(&vars),
+            eval_postfix(&postfix, &vars),
+            "{input}"
+        );
+    }
+    assert_eq!(compile("x * 2 + 3 * 4").unwrap().code_len(), 17);
+    assert_eq!(
+        compile("x + z").unwrap().run(&vars),
+        Err(ExprError::UnknownVariable {
+            col: 5,
+            name: "z".to_owned()
+        })
+    );
+    assert_eq!(
+        Program::<i64>::compile(&[Token::Num(1), Token::Num(2)]).unwrap_err(),
+        ExprError::InvalidPostfix
+    );
+
+    let lines = ["1 + 2", "", "1 / 0"].map(String::from);
+    let err = run_bench(&lines, &p).unwrap_err();
+    assert_eq!(err.to_string(), "line 3: division by zero\n    1 / 0");
+    let lines = ["let x = 2", "x + y"].map(String::from);
+    let err = run_bench(&lines, &p).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "line 2: `y` is not defined\n    x + y\n        ^"
+    );
+}
+
+#[test]
+fn parallel() {
+    let input = fs::read_to_string("src/day18/input.txt").unwrap();
+    let lines = Day18::parse(&input).unwrap();
+    for p in [left_to_right(), addition_first(), standard()] {
+        assert_eq!(
+            sum_lines_parallel::<i64>(&lines, &p).unwrap(),
+            sum_lines::<i64>(&lines, &p).unwrap()
+        );
+    }
+
+    let program: Vec<String> = [
+        "let x = 2",
+        "x * 3",
+        "let x = x + 1",
+        "",
+        "x * 3",
+        "let y = x",
+    ]
+    .map(String::from)
+    .into();
+    assert_eq!(
+        sum_lines_parallel::<i64>(&program, &standard()).unwrap(),
+        15
+    );
+
+    let lines: Vec<String> = ["1 + 2", "1 / 0", "let x = y", "(", "9223372036854775807"]
+        .map(String::from)
+        .into();
+    for end in 1..=lines.len() {
+        let lines = &lines[..end];
+        assert_eq!(
+            sum_lines_parallel::<i64>(lines, &standard()).map_err(|e| e.to_string()),
+            sum_lines::<i64>(lines, &standard()).map_err(|e| e.to_string())
+        );
+    }
+}
+
+#[test]
+fn simplification() {
+    let p = standard();
+    let simple = |input: &str| simplify::simplify(&ast::parse(input, &p).unwrap()).to_string();
+    assert_eq!(simple("(1 + 2) * 3 ^ 2 - 4 / 3"), "26");
+    assert_eq!(simple("y + 1 + (x + 2)"), "3 + x + y");
+    assert_eq!(simple("2 * b * (a * 3)"), "6 * a * b");
+    assert_eq!(simple("x - y"), "x + (-1) * y");
+    assert_eq!(simple("--x * 1 + 0"), "x");
+    assert_eq!(simple("(x + 1) * 2 % (4 / 2)"), "(2 * (1 + x)) % 2");
+    assert_eq!(simple("1 / 0 + 2"), "2 + (1 / 0)");
+    assert_eq!(
+        simplify::simplify(&ast::parse("b * a + 1", &p).unwrap()),
+        simplify::simplify(&ast::parse("1 + (a * b)", &p).unwrap())
+    );
+
+    let (a, b) = (left_to_right(), addition_first());
+    let compare = |input| simplify::compare(input, &a, &b).unwrap();
+    assert_eq!(compare("1 + 2 * 3"), Equivalence::Same);
+    assert_eq!(compare("2 * 3 + 1"), Equivalence::Different);
+    assert_eq!(compare("x * y + z * w"), Equivalence::Unknown);
+    assert_eq!(compare("let v = x + y * 2"), Equivalence::Same);
+    assert_eq!(compare("2 * x + 0"), Equivalence::Same);
+}
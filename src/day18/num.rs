@@ -0,0 +1,287 @@
+use std::{cmp::Ordering, fmt, num::Wrapping};
+
+use super::error::ExprError;
+
+/// Arithmetic backend the evaluators are generic over.
+///
+/// * `i64` uses checked arithmetic and reports overflow as an error,
+/// * `Wrapping<i64>` wraps around modulo 2^64,
+/// * [`BigInt`] never overflows.
+pub trait Number: Clone + fmt::Display + Send + Sync {
+    fn from_i64(n: i64) -> Self;
+    fn apply(op: char, lhs: Self, rhs: Self) -> Result<Self, ExprError>;
+    fn neg(self) -> Result<Self, ExprError>;
+}
+
+impl Number for i64 {
+    fn from_i64(n: i64) -> Self {
+        n
+    }
+
+    fn apply(op: char, lhs: Self, rhs: Self) -> Result<Self, ExprError> {
+        let result = match op {
+            '+' => lhs.checked_add(rhs),
+            '-' => lhs.checked_sub(rhs),
+            '*' => lhs.checked_mul(rhs),
+            '/' | '%' if rhs == 0 => return Err(ExprError::DivisionByZero),
+            '/' => lhs.checked_div(rhs),
+            '%' => lhs.checked_rem(rhs),
+            '^' => {
+                let exp =
+                    u32::try_from(rhs).map_err(|_| ExprError::InvalidExponent(rhs.to_string()))?;
+                lhs.checked_pow(exp)
+            }
+            op => unreachable!("unknown operator `{op}`"),
+        };
+        result.ok_or(ExprError::Overflow)
+    }
+
+    fn neg(self) -> Result<Self, ExprError> {
+        self.checked_neg().ok_or(ExprError::Overflow)
+    }
+}
+
+impl Number for Wrapping<i64> {
+    fn from_i64(n: i64) -> Self {
+        Wrapping(n)
+    }
+
+    fn apply(op: char, Wrapping(lhs): Self, Wrapping(rhs): Self) -> Result<Self, ExprError> {
+        let result = match op {
+            '+' => lhs.wrapping_add(rhs),
+            '-' => lhs.wrapping_sub(rhs),
+            '*' => lhs.wrapping_mul(rhs),
+            '/' | '%' if rhs == 0 => return Err(ExprError::DivisionByZero),
+            '/' => lhs.wrapping_div(rhs),
+            '%' => lhs.wrapping_rem(rhs),
+            '^' => {
+                let exp =
+                    u32::try_from(rhs).map_err(|_| ExprError::InvalidExponent(rhs.to_string()))?;
+                lhs.wrapping_pow(exp)
+            }
+            op => unreachable!("unknown operator `{op}`"),
+        };
+        Ok(Wrapping(result))
+    }
+
+    fn neg(self) -> Result<Self, ExprError> {
+        Ok(-self)
+    }
+}
+
+/// Signed arbitrary-precision integer.
+///
+/// Exponentiation fails with [`ExprError::Overflow`] once the result would exceed
+/// [`BigInt::MAX_BITS`], so that a huge exponent can't stall the evaluation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BigInt {
+    negative: bool,
+    /// little-endian base 2^32 digits without trailing zeros, empty for zero
+    mag: Vec<u32>,
+}
+
+impl BigInt {
+    pub const MAX_BITS: u64 = 1 << 16;
+
+    fn new(negative: bool, mag: Vec<u32>) -> Self {
+        let mag = trim(mag);
+        let negative = negative && !mag.is_empty();
+        Self { negative, mag }
+    }
+
+    fn is_zero(&self) -> bool {
+        self.mag.is_empty()
+    }
+
+    fn bits(&self) -> u64 {
+        match self.mag.last() {
+            Some(top) => self.mag.len() as u64 * 32 - u64::from(top.leading_zeros()),
+            None => 0,
+        }
+    }
+
+    fn add(self, rhs: Self) -> Self {
+        if self.negative == rhs.negative {
+            return Self::new(self.negative, add_mag(&self.mag, &rhs.mag));
+        }
+        match cmp_mag(&self.mag, &rhs.mag) {
+            Ordering::Less => Self::new(rhs.negative, sub_mag(&rhs.mag, &self.mag)),
+            _ => Self::new(self.negative, sub_mag(&self.mag, &rhs.mag)),
+        }
+    }
+
+    fn mul(&self, rhs: &Self) -> Self {
+        Self::new(self.negative != rhs.negative, mul_mag(&self.mag, &rhs.mag))
+    }
+
+    /// Division truncating towards zero, the remainder has the sign of `self`, like `i64`.
+    fn div_rem(&self, rhs: &Self) -> (Self, Self) {
+        let (q, r) = div_rem_mag(&self.mag, &rhs.mag);
+        (
+            Self::new(self.negative != rhs.negative, q),
+            Self::new(self.negative, r),
+        )
+    }
+
+    fn pow(&self, mut exp: u32) -> Self {
+        let mut base = self.clone();
+        let mut result = Self::from_i64(1);
+        while exp > 0 {
+            if exp & 1 == 1 {
+                result = result.mul(&base);
+            }
+            base = base.mul(&base);
+            exp >>= 1;
+        }
+        result
+    }
+}
+
+impl Number for BigInt {
+    fn from_i64(n: i64) -> Self {
+        let abs = n.unsigned_abs();
+        Self::new(n < 0, vec![abs as u32, (abs >> 32) as u32])
+    }
+
+    fn apply(op: char, lhs: Self, rhs: Self) -> Result<Self, ExprError> {
+        match op {
+            '+' => Ok(lhs.add(rhs)),
+            '-' => Ok(lhs.add(rhs.neg()?)),
+            '*' => Ok(lhs.mul(&rhs)),
+            '/' | '%' if rhs.is_zero() => Err(ExprError::DivisionByZero),
+            '/' => Ok(lhs.div_rem(&rhs).0),
+            '%' => Ok(lhs.div_rem(&rhs).1),
+            '^' => match rhs.mag[..] {
+                [] => Ok(Self::from_i64(1)),
+                // the result has at least `(bits - 1) * exp + 1` bits
+                [exp] if !rhs.negative => match lhs.bits().saturating_sub(1) * u64::from(exp) {
+                    bits if bits >= Self::MAX_BITS => Err(ExprError::Overflow),
+                    _ => Ok(lhs.pow(exp)),
+                },
+                _ => Err(ExprError::InvalidExponent(rhs.to_string())),
+            },
+            op => unreachable!("unknown operator `{op}`"),
+        }
+    }
+
+    fn neg(self) -> Result<Self, ExprError> {
+        Ok(Self::new(!self.negative, self.mag))
+    }
+}
+
+impl fmt::Display for BigInt {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const CHUNK: u32 = 1_000_000_000;
+
+        if self.is_zero() {
+            return write!(f, "0");
+        }
+        let mut chunks = Vec::new();
+        let mut mag = self.mag.clone();
+        while !mag.is_empty() {
+            let (q, r) = div_rem_mag(&mag, &[CHUNK]);
+            chunks.push(r.first().copied().unwrap_or(0));
+            mag = q;
+        }
+
+        if self.negative {
+            write!(f, "-")?;
+        }
+        let mut chunks = chunks.iter().rev();
+        write!(f, "{}", chunks.next().unwrap())?;
+        for chunk in chunks {
+            write!(f, "{chunk:09}")?;
+        }
+        Ok(())
+    }
+}
+
+fn trim(mut mag: Vec<u32>) -> Vec<u32> {
+    while mag.last() == Some(&0) {
+        mag.pop();
+    }
+    mag
+}
+
+fn cmp_mag(a: &[u32], b: &[u32]) -> Ordering {
+    a.len()
+        .cmp(&b.len())
+        .then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+fn add_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len().max(b.len()) + 1);
+    let mut carry = 0u64;
+    for i in 0..a.len().max(b.len()) {
+        let sum = *a.get(i).unwrap_or(&0) as u64 + *b.get(i).unwrap_or(&0) as u64 + carry;
+        result.push(sum as u32);
+        carry = sum >> 32;
+    }
+    result.push(carry as u32);
+    trim(result)
+}
+
+/// Computes `a - b`, `a` must not be smaller than `b`.
+fn sub_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = Vec::with_capacity(a.len());
+    let mut borrow = 0i64;
+    for (i, &x) in a.iter().enumerate() {
+        let mut diff = x as i64 - *b.get(i).unwrap_or(&0) as i64 - borrow;
+        borrow = 0;
+        if diff < 0 {
+            diff += 1 << 32;
+            borrow = 1;
+        }
+        result.push(diff as u32);
+    }
+    trim(result)
+}
+
+fn mul_mag(a: &[u32], b: &[u32]) -> Vec<u32> {
+    let mut result = vec![0u32; a.len() + b.len()];
+    for (i, &x) in a.iter().enumerate() {
+        let mut carry = 0u64;
+        for (j, &y) in b.iter().enumerate() {
+            let cur = result[i + j] as u64 + x as u64 * y as u64 + carry;
+            result[i + j] = cur as u32;
+            carry = cur >> 32;
+        }
+        result[i + b.len()] = carry as u32;
+    }
+    trim(result)
+}
+
+/// Binary long division of magnitudes, `b` must not be zero.
+fn div_rem_mag(a: &[u32], b: &[u32]) -> (Vec<u32>, Vec<u32>) {
+    if let [d] = b {
+        let d = *d as u64;
+        let mut q = vec![0u32; a.len()];
+        let mut r = 0u64;
+        for i in (0..a.len()).rev() {
+            let cur = (r << 32) | a[i] as u64;
+            q[i] = (cur / d) as u32;
+            r = cur % d;
+        }
+        return (trim(q), trim(vec![r as u32]));
+    }
+
+    let mut q = vec![0u32; a.len()];
+    let mut r: Vec<u32> = Vec::new();
+    for i in (0..a.len() * 32).rev() {
+        // r = r << 1 | bit i of a
+        let mut carry = (a[i / 32] >> (i % 32)) & 1;
+        for limb in r.iter_mut() {
+            let next = *limb >> 31;
+            *limb = (*limb << 1) | carry;
+            carry = next;
+        }
+        if carry != 0 {
+            r.push(carry);
+        }
+        if cmp_mag(&r, b) != Ordering::Less {
+            r = sub_mag(&r, b);
+            q[i / 32] |= 1 << (i % 32);
+        }
+    }
+    (trim(q), r)
+}
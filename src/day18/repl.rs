@@ -0,0 +1,146 @@
+use std::{
+    cmp::Reverse,
+    io::{self, BufRead, Write},
+};
+
+use super::{
+    ast::{self, Stmt},
+    error::ExprError,
+    is_binary_operator,
+    num::Number,
+    parse_precedence, parse_profile, Assoc, Precedence, Vars,
+};
+
+const HELP: &str = "\
+<expr>               print the postfix form, the tree and the value of <expr>,
+                     `$n` refers to the n-th result
+let <name> = <expr>  like <expr>, but binds the value to <name>
+:prec                show the precedence table
+:prec <op> <n>       set the precedence of <op>, from 0 to 1000
+:assoc <op> <dir>    make <op> `left` or `right` associative
+:profile <profile>   replace the table with a named or custom profile
+:help                show this message
+:quit                exit";
+
+/// Reads expressions and commands from stdin until EOF or `:quit`.
+pub fn run<N: Number>(precedence: Precedence) -> anyhow::Result<()> {
+    let mut repl = Repl::<N>::new(precedence);
+    let mut stdout = io::stdout();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        print!("> ");
+        stdout.flush()?;
+        let Some(line) = lines.next().transpose()? else {
+            println!();
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        match repl.eval(&line) {
+            Ok(Some(out)) => println!("{out}"),
+            Ok(None) => break,
+            Err(e) => println!("error: {e}"),
+        }
+    }
+    Ok(())
+}
+
+pub struct Repl<N> {
+    precedence: Precedence,
+    /// `let` bindings and previous results, bound to `$1`, `$2`, ...
+    vars: Vars<N>,
+    results: usize,
+}
+
+impl<N: Number> Repl<N> {
+    pub fn new(precedence: Precedence) -> Self {
+        Self {
+            precedence,
+            vars: Vars::new(),
+            results: 0,
+        }
+    }
+
+    /// Handles one line of input, returning what to print or `None` to quit.
+    pub fn eval(&mut self, line: &str) -> Result<Option<String>, String> {
+        let line = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            return self.expr(line).map(Some);
+        };
+
+        let mut words = command.split_whitespace();
+        let out = match (words.next(), words.next(), words.next(), words.next()) {
+            (Some("prec"), None, ..) => self.table(),
+            (Some("prec"), Some(op), Some(prec), None) => {
+                let op = operator(op)?;
+                let prec = parse_precedence(prec)
+                    .ok_or_else(|| format!("invalid precedence: `{prec}`"))?;
+                let assoc = self.precedence.get(&op).map_or(Assoc::Left, |&(_, a)| a);
+                self.precedence.insert(op, (prec, assoc));
+                self.table()
+            }
+            (Some("assoc"), Some(op), Some(dir), None) => {
+                let op = operator(op)?;
+                let assoc = match dir {
+                    "left" => Assoc::Left,
+                    "right" => Assoc::Right,
+                    _ => return Err(format!("expected `left` or `right`, got `{dir}`")),
+                };
+                let entry = self
+                    .precedence
+                    .get_mut(&op)
+                    .ok_or_else(|| format!("`{op}` has no precedence yet, use `:prec` first"))?;
+                entry.1 = assoc;
+                self.table()
+            }
+            (Some("profile"), Some(name), None, _) => {
+                self.precedence = parse_profile(name)?;
+                self.table()
+            }
+            (Some("help"), None, ..) => HELP.to_owned(),
+            (Some("quit"), None, ..) => return Ok(None),
+            _ => return Err(format!("unknown command: `{line}`, try `:help`")),
+        };
+        Ok(Some(out))
+    }
+
+    fn expr(&mut self, line: &str) -> Result<String, String> {
+        let render = |e: ExprError| e.render(line);
+        let (name, expr) = match ast::parse_stmt(line, &self.precedence).map_err(render)? {
+            Stmt::Let(name, expr) => (name, expr),
+            Stmt::Expr(expr) => (format!("${}", self.results + 1), expr),
+        };
+        let value: N = expr.eval(&self.vars).map_err(render)?;
+
+        let postfix: Vec<String> = expr.to_postfix().iter().map(|t| t.to_string()).collect();
+        let out = format!(
+            "postfix: {}\ntree:    {}\n{name} = {value}",
+            postfix.join(" "),
+            expr.tree()
+        );
+        if name.starts_with('$') {
+            self.results += 1;
+        }
+        self.vars.insert(name, value);
+        Ok(out)
+    }
+
+    /// The precedence table, tightest binding operators first.
+    fn table(&self) -> String {
+        let mut ops: Vec<_> = self.precedence.iter().collect();
+        ops.sort_by_key(|&(op, &(prec, _))| (Reverse(prec), *op));
+        ops.iter()
+            .map(|(op, (prec, assoc))| format!("{op} {prec} {assoc:?}").to_lowercase())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+fn operator(s: &str) -> Result<char, String> {
+    let mut chars = s.chars();
+    match (chars.next(), chars.next()) {
+        (Some(op), None) if is_binary_operator(op) => Ok(op),
+        _ => Err(format!("unknown operator: `{s}`")),
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+use super::{
+    ast::{self, Expr, Stmt},
+    error::ExprError,
+    num::Number,
+    Precedence,
+};
+
+/// Normal form of an expression.
+///
+/// Subtraction and negation are rewritten as multiplication by -1, chains of `+` and `*` are
+/// flattened with their operands sorted and their literals folded into a single constant.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Simple {
+    Num(i64),
+    Var(String),
+    Sum(Vec<Simple>),
+    Product(Vec<Simple>),
+    /// `/`, `%` or `^`, which are neither associative nor commutative
+    BinOp(char, Box<Simple>, Box<Simple>),
+}
+
+/// Result of comparing an expression under two precedence profiles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Equivalence {
+    Same,
+    Different,
+    /// The normal forms differ, but contain variables or operations that couldn't be folded,
+    /// so the expressions may still evaluate identically.
+    Unknown,
+}
+
+pub fn simplify(expr: &Expr) -> Simple {
+    match expr {
+        Expr::Num(n) => Simple::Num(*n),
+        Expr::Var(name, _) => Simple::Var(name.clone()),
+        Expr::Group(x) => simplify(x),
+        Expr::Neg(x) => product(vec![Simple::Num(-1), simplify(x)]),
+        Expr::BinOp('+', lhs, rhs) => sum(vec![simplify(lhs), simplify(rhs)]),
+        Expr::BinOp('-', lhs, rhs) => sum(vec![
+            simplify(lhs),
+            product(vec![Simple::Num(-1), simplify(rhs)]),
+        ]),
+        Expr::BinOp('*', lhs, rhs) => product(vec![simplify(lhs), simplify(rhs)]),
+        Expr::BinOp(op, lhs, rhs) => match (simplify(lhs), simplify(rhs)) {
+            (Simple::Num(a), Simple::Num(b)) => match i64::apply(*op, a, b) {
+                Ok(n) => Simple::Num(n),
+                Err(_) => Simple::BinOp(*op, Box::new(Simple::Num(a)), Box::new(Simple::Num(b))),
+            },
+            (lhs, rhs) => Simple::BinOp(*op, Box::new(lhs), Box::new(rhs)),
+        },
+    }
+}
+
+/// Parses `input` under both profiles and compares the normal forms.
+pub fn compare(input: &str, a: &Precedence, b: &Precedence) -> Result<Equivalence, ExprError> {
+    let parse = |precedence| match ast::parse_stmt(input, precedence)? {
+        Stmt::Let(_, expr) | Stmt::Expr(expr) => Ok(simplify(&expr)),
+    };
+    Ok(match (parse(a)?, parse(b)?) {
+        (a, b) if a == b => Equivalence::Same,
+        (Simple::Num(_), Simple::Num(_)) => Equivalence::Different,
+        _ => Equivalence::Unknown,
+    })
+}
+
+fn sum(terms: Vec<Simple>) -> Simple {
+    fold(terms, '+', 0, |terms| match terms {
+        Simple::Sum(terms) => Ok(terms),
+        term => Err(term),
+    })
+    .unwrap_or_else(Simple::Sum)
+}
+
+fn product(factors: Vec<Simple>) -> Simple {
+    fold(factors, '*', 1, |factors| match factors {
+        Simple::Product(factors) => Ok(factors),
+        factor => Err(factor),
+    })
+    .unwrap_or_else(Simple::Product)
+}
+
+/// Flattens nested chains of `op`, folds the literals and sorts the operands. Returns the
+/// operands if more than one remains.
+fn fold(
+    operands: Vec<Simple>,
+    op: char,
+    identity: i64,
+    flatten: impl Fn(Simple) -> Result<Vec<Simple>, Simple>,
+) -> Result<Simple, Vec<Simple>> {
+    let mut constant = identity;
+    let mut rest = Vec::new();
+    let mut stack = operands;
+    while let Some(operand) = stack.pop() {
+        match flatten(operand) {
+            Ok(nested) => stack.extend(nested),
+            Err(Simple::Num(n)) => match i64::apply(op, constant, n) {
+                Ok(folded) => constant = folded,
+                Err(_) => rest.push(Simple::Num(n)),
+            },
+            Err(operand) => rest.push(operand),
+        }
+    }
+
+    if constant != identity || rest.is_empty() {
+        rest.push(Simple::Num(constant));
+    }
+    rest.sort();
+    match rest.len() {
+        1 => Ok(rest.pop().unwrap()),
+        _ => Err(rest),
+    }
+}
+
+impl fmt::Display for Simple {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter, operands: &[Simple], sep: &str| {
+            for (i, x) in operands.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {sep} ")?;
+                }
+                match x {
+                    // products bind tighter than sums
+                    Simple::Product(_) if sep == "+" => write!(f, "{x}")?,
+                    x => x.fmt_operand(f)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Simple::Num(n) => write!(f, "{n}"),
+            Simple::Var(name) => write!(f, "{name}"),
+            Simple::Sum(terms) => join(f, terms, "+"),
+            Simple::Product(factors) => join(f, factors, "*"),
+            Simple::BinOp(op, lhs, rhs) => {
+                lhs.fmt_operand(f)?;
+                write!(f, " {op} ")?;
+                rhs.fmt_operand(f)
+            }
+        }
+    }
+}
+
+impl Simple {
+    /// Formats `self` as an operand, in parentheses unless it's a variable or a non-negative
+    /// number.
+    fn fmt_operand(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Simple::Num(n) if *n >= 0 => write!(f, "{n}"),
+            Simple::Var(_) => write!(f, "{self}"),
+            _ => write!(f, "({self})"),
+        }
+    }
+}
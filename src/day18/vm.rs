@@ -0,0 +1,179 @@
+use super::{error::ExprError, num::Number, Token, Vars};
+
+const PUSH: u8 = 0;
+const LOAD: u8 = 1;
+const NEG: u8 = 2;
+const ADD: u8 = 3;
+const SUB: u8 = 4;
+const MUL: u8 = 5;
+const DIV: u8 = 6;
+const REM: u8 = 7;
+const POW: u8 = 8;
+
+/// A postfix program lowered to bytecode for a stack machine.
+///
+/// `PUSH` and `LOAD` are followed by a little-endian `u32` index into the constants and the
+/// variables respectively, every other opcode is a single byte.
+#[derive(Debug, Clone)]
+pub struct Program<N> {
+    code: Vec<u8>,
+    consts: Vec<N>,
+    /// referenced variables, with the column of their first reference
+    vars: Vec<(String, usize)>,
+    max_stack: usize,
+}
+
+impl<N: Number> Program<N> {
+    /// Lowers `postfix` to bytecode, folding subexpressions that only involve literals.
+    pub fn compile(postfix: &[Token]) -> Result<Self, ExprError> {
+        let mut compiler = Compiler {
+            program: Program {
+                code: Vec::new(),
+                consts: Vec::new(),
+                vars: Vec::new(),
+                max_stack: 0,
+            },
+            pending: Vec::new(),
+            depth: 0,
+        };
+        for token in postfix {
+            compiler.token(token)?;
+        }
+        compiler.flush();
+        if compiler.depth != 1 {
+            return Err(ExprError::InvalidPostfix);
+        }
+        Ok(compiler.program)
+    }
+
+    pub fn code_len(&self) -> usize {
+        self.code.len()
+    }
+
+    pub fn run(&self, vars: &Vars<N>) -> Result<N, ExprError> {
+        let vars = self
+            .vars
+            .iter()
+            .map(|(name, col)| {
+                vars.get(name)
+                    .cloned()
+                    .ok_or_else(|| ExprError::UnknownVariable {
+                        col: *col,
+                        name: name.clone(),
+                    })
+            })
+            .collect::<Result<Vec<N>, _>>()?;
+
+        let mut stack: Vec<N> = Vec::with_capacity(self.max_stack);
+        let mut ip = 0;
+        while let Some(&opcode) = self.code.get(ip) {
+            ip += 1;
+            match opcode {
+                PUSH | LOAD => {
+                    let bytes = self.code[ip..ip + 4].try_into().unwrap();
+                    let index = u32::from_le_bytes(bytes) as usize;
+                    ip += 4;
+                    let pool = if opcode == PUSH { &self.consts } else { &vars };
+                    stack.push(pool[index].clone());
+                }
+                NEG => {
+                    let x = stack.pop().unwrap();
+                    stack.push(x.neg()?);
+                }
+                _ => {
+                    let op = match opcode {
+                        ADD => '+',
+                        SUB => '-',
+                        MUL => '*',
+                        DIV => '/',
+                        REM => '%',
+                        POW => '^',
+                        _ => unreachable!("invalid opcode {opcode}"),
+                    };
+                    let rhs = stack.pop().unwrap();
+                    let lhs = stack.pop().unwrap();
+                    stack.push(N::apply(op, lhs, rhs)?);
+                }
+            }
+        }
+        Ok(stack.pop().unwrap())
+    }
+}
+
+struct Compiler<N> {
+    program: Program<N>,
+    /// constants on top of the stack that haven't been emitted yet, because they may still
+    /// be folded
+    pending: Vec<N>,
+    /// number of stack entries below `pending`, all computed by the emitted code
+    depth: usize,
+}
+
+impl<N: Number> Compiler<N> {
+    fn token(&mut self, token: &Token) -> Result<(), ExprError> {
+        match token {
+            Token::Num(n) => self.pending.push(N::from_i64(*n)),
+            Token::Var(name, col) => {
+                self.flush();
+                let index = match self.program.vars.iter().position(|(v, _)| v == name) {
+                    Some(index) => index,
+                    None => {
+                        self.program.vars.push((name.clone(), *col));
+                        self.program.vars.len() - 1
+                    }
+                };
+                self.emit_indexed(LOAD, index);
+                self.grow();
+            }
+            Token::Neg => match self.pending.pop() {
+                Some(x) => self.pending.push(x.neg()?),
+                None if self.depth >= 1 => self.program.code.push(NEG),
+                None => return Err(ExprError::InvalidPostfix),
+            },
+            op => {
+                if self.pending.len() >= 2 {
+                    let rhs = self.pending.pop().unwrap();
+                    let lhs = self.pending.pop().unwrap();
+                    self.pending.push(N::apply(op.symbol(), lhs, rhs)?);
+                    return Ok(());
+                }
+                self.flush();
+                if self.depth < 2 {
+                    return Err(ExprError::InvalidPostfix);
+                }
+                self.program.code.push(match op {
+                    Token::Add => ADD,
+                    Token::Sub => SUB,
+                    Token::Mul => MUL,
+                    Token::Div => DIV,
+                    Token::Rem => REM,
+                    Token::Pow => POW,
+                    Token::Num(_) | Token::Var(..) | Token::Neg => unreachable!(),
+                });
+                self.depth -= 1;
+            }
+        }
+        Ok(())
+    }
+
+    /// Emits the pending constants, bottom first.
+    fn flush(&mut self) {
+        for value in std::mem::take(&mut self.pending) {
+            self.program.consts.push(value);
+            self.emit_indexed(PUSH, self.program.consts.len() - 1);
+            self.grow();
+        }
+    }
+
+    fn emit_indexed(&mut self, opcode: u8, index: usize) {
+        self.program.code.push(opcode);
+        self.program
+            .code
+            .extend_from_slice(&(index as u32).to_le_bytes());
+    }
+
+    fn grow(&mut self) {
+        self.depth += 1;
+        self.program.max_stack = self.program.max_stack.max(self.depth);
+    }
+}
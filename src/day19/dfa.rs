@@ -0,0 +1,173 @@
+use std::collections::{BTreeSet, HashMap};
+
+use thiserror::Error;
+
+use super::{Rule, Rules};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum CompileError {
+    #[error("rule {0} is recursive, so the grammar can't be compiled to a finite automaton")]
+    Recursive(u32),
+    #[error("rule {0} is referenced but never defined")]
+    Undefined(u32),
+}
+
+/// Deterministic automaton accepting exactly the messages matched by rule 0.
+///
+/// Built by expanding the rules into an NFA and running the subset construction over it.
+/// Only grammars without recursive rules can be compiled, for the rest the [`Matcher`] has to
+/// be used.
+///
+/// [`Matcher`]: super::matcher::Matcher
+pub struct Dfa {
+    transitions: Vec<[u32; 256]>,
+    accepting: Vec<bool>,
+}
+
+/// Transitions that can't lead to an accepting state go here.
+const DEAD: u32 = 0;
+
+impl Dfa {
+    pub fn compile(rules: &Rules) -> Result<Self, CompileError> {
+        let mut nfa = Nfa {
+            rules,
+            states: vec![NfaState::default()],
+            expanding: Vec::new(),
+        };
+        let last = nfa.build(&Rule::Ref(0), 0)?;
+        Ok(nfa.determinize(last))
+    }
+
+    pub fn matches(&self, message: &str) -> bool {
+        let mut state = 1;
+        for &byte in message.as_bytes() {
+            state = self.transitions[state as usize][byte as usize];
+            if state == DEAD {
+                return false;
+            }
+        }
+        self.accepting[state as usize]
+    }
+
+    pub fn state_count(&self) -> usize {
+        self.transitions.len()
+    }
+}
+
+#[derive(Default)]
+struct NfaState {
+    eps: Vec<usize>,
+    bytes: Vec<(u8, usize)>,
+}
+
+struct Nfa<'a> {
+    rules: &'a Rules,
+    states: Vec<NfaState>,
+    /// rules currently being expanded, used to detect recursion
+    expanding: Vec<u32>,
+}
+
+impl Nfa<'_> {
+    fn add_state(&mut self) -> usize {
+        self.states.push(NfaState::default());
+        self.states.len() - 1
+    }
+
+    /// Adds the states for `rule` starting at `from`, returning the state where it ends.
+    fn build(&mut self, rule: &Rule, from: usize) -> Result<usize, CompileError> {
+        match rule {
+            Rule::Ref(id) => {
+                if self.expanding.contains(id) {
+                    return Err(CompileError::Recursive(*id));
+                }
+                let rules = self.rules;
+                let rule = rules.get(id).ok_or(CompileError::Undefined(*id))?;
+                self.expanding.push(*id);
+                let end = self.build(rule, from)?;
+                self.expanding.pop();
+                Ok(end)
+            }
+            Rule::Lit(lit) => {
+                let mut state = from;
+                for &byte in lit.as_bytes() {
+                    let next = self.add_state();
+                    self.states[state].bytes.push((byte, next));
+                    state = next;
+                }
+                Ok(state)
+            }
+            Rule::Seq(seq) => self.build_seq(seq, from),
+            Rule::Alt(alts) => {
+                let end = self.add_state();
+                for branch in alts {
+                    let start = self.add_state();
+                    self.states[from].eps.push(start);
+                    let last = self.build_seq(branch, start)?;
+                    self.states[last].eps.push(end);
+                }
+                Ok(end)
+            }
+        }
+    }
+
+    fn build_seq(&mut self, seq: &[Rule], from: usize) -> Result<usize, CompileError> {
+        let mut state = from;
+        for rule in seq {
+            state = self.build(rule, state)?;
+        }
+        Ok(state)
+    }
+
+    fn closure(&self, states: impl IntoIterator<Item = usize>) -> BTreeSet<usize> {
+        let mut set = BTreeSet::new();
+        let mut stack: Vec<usize> = states.into_iter().collect();
+        while let Some(s) = stack.pop() {
+            if set.insert(s) {
+                stack.extend(&self.states[s].eps);
+            }
+        }
+        set
+    }
+
+    fn determinize(&self, last: usize) -> Dfa {
+        let mut dfa = Dfa {
+            transitions: vec![[DEAD; 256]],
+            accepting: vec![false],
+        };
+        let mut ids: HashMap<BTreeSet<usize>, u32> = HashMap::new();
+        let mut queue = Vec::new();
+
+        let start = self.closure([0]);
+        ids.insert(start.clone(), 1);
+        dfa.transitions.push([DEAD; 256]);
+        dfa.accepting.push(start.contains(&last));
+        queue.push(start);
+
+        while let Some(set) = queue.pop() {
+            let id = ids[&set];
+            let mut moves: HashMap<u8, Vec<usize>> = HashMap::new();
+            for &s in &set {
+                for &(byte, next) in &self.states[s].bytes {
+                    moves.entry(byte).or_default().push(next);
+                }
+            }
+            for (byte, targets) in moves {
+                let target = self.closure(targets);
+                let next = match ids.get(&target) {
+                    Some(&next) => next,
+                    None => {
+                        let next = dfa.transitions.len() as u32;
+                        ids.insert(target.clone(), next);
+                        dfa.transitions.push([DEAD; 256]);
+                        dfa.accepting.push(target.contains(&last));
+                        queue.push(target);
+                        next
+                    }
+                };
+                dfa.transitions[id as usize][byte as usize] = next;
+            }
+        }
+
+        dfa
+    }
+}
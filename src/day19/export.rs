@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+
+use super::{Rule, Rules};
+
+/// Pretty-prints the rules as EBNF, one production per rule in ascending id order.
+///
+/// With `collapse` set, rules referenced exactly once are inlined at their use site instead of
+/// getting their own production.
+pub fn to_ebnf(rules: &Rules, collapse: bool) -> String {
+    let inline = if collapse {
+        inlined_rules(rules)
+    } else {
+        HashSet::new()
+    };
+    let printer = Ebnf {
+        rules,
+        inline: |id: u32| inline.contains(&id),
+    };
+
+    let mut out = String::new();
+    for id in rules.keys().copied().sorted() {
+        if printer.should_inline(id) {
+            continue;
+        }
+        let mut stack = vec![id];
+        let body = printer.body(&rules[&id], &mut stack);
+        out.push_str(&format!("r{id} = {body} ;\n"));
+    }
+    out
+}
+
+/// The rules other than 0 that are referenced exactly once and can be inlined without a trace.
+///
+/// A rule that would still be referenced by name, e.g. from its own body, or that isn't reached
+/// from any production, e.g. because its only use is in another such rule, gets its own
+/// production after all.
+fn inlined_rules(rules: &Rules) -> HashSet<u32> {
+    let mut uses: HashMap<u32, usize> = HashMap::new();
+    for rule in rules.values() {
+        for_each_ref(rule, &mut |id| *uses.entry(id).or_default() += 1);
+    }
+    let mut inline: HashSet<u32> = rules
+        .keys()
+        .copied()
+        .filter(|&id| id != 0 && uses.get(&id) == Some(&1))
+        .collect();
+
+    loop {
+        let mut expanded = HashSet::new();
+        let mut named = HashSet::new();
+        for (&id, rule) in rules {
+            if !inline.contains(&id) {
+                let mut stack = vec![id];
+                walk(rules, &inline, rule, &mut stack, &mut expanded, &mut named);
+            }
+        }
+        let stranded = inline
+            .iter()
+            .copied()
+            .filter(|id| !expanded.contains(id) || named.contains(id))
+            .min();
+        match stranded {
+            Some(id) => inline.remove(&id),
+            None => return inline,
+        };
+    }
+}
+
+/// Follows the references of `rule` the way [`Ebnf::body`] prints them, collecting the inlined
+/// rules in `expanded` and the ones referenced by name in `named`.
+fn walk(
+    rules: &Rules,
+    inline: &HashSet<u32>,
+    rule: &Rule,
+    stack: &mut Vec<u32>,
+    expanded: &mut HashSet<u32>,
+    named: &mut HashSet<u32>,
+) {
+    let mut refs = Vec::new();
+    for_each_ref(rule, &mut |id| refs.push(id));
+    for id in refs {
+        if inline.contains(&id) && !stack.contains(&id) {
+            expanded.insert(id);
+            stack.push(id);
+            walk(rules, inline, &rules[&id], stack, expanded, named);
+            stack.pop();
+        } else {
+            named.insert(id);
+        }
+    }
+}
+
+/// Renders the rule references as a Graphviz DOT dependency graph. Rules that are plain
+/// literals are labeled with the literal they match.
+pub fn to_dot(rules: &Rules) -> String {
+    let mut out = String::from("digraph rules {\n");
+    for id in rules.keys().copied().sorted() {
+        let label = match &rules[&id] {
+            Rule::Lit(lit) => format!(
+                "{id}: {}",
+                quote(lit).replace('\\', "\\\\").replace('"', "\\\"")
+            ),
+            _ => id.to_string(),
+        };
+        out.push_str(&format!("    {id} [label=\"{label}\"];\n"));
+    }
+    for id in rules.keys().copied().sorted() {
+        let mut refs = Vec::new();
+        for_each_ref(&rules[&id], &mut |r| refs.push(r));
+        for r in refs.into_iter().unique() {
+            out.push_str(&format!("    {id} -> {r};\n"));
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn for_each_ref(rule: &Rule, f: &mut impl FnMut(u32)) {
+    match rule {
+        Rule::Ref(id) => f(*id),
+        Rule::Lit(_) => {}
+        Rule::Seq(seq) => seq.iter().for_each(|r| for_each_ref(r, f)),
+        Rule::Alt(alts) => alts.iter().flatten().for_each(|r| for_each_ref(r, f)),
+    }
+}
+
+/// Quotes a literal the same way the rules are written in the input.
+fn quote(lit: &str) -> String {
+    let mut out = String::from('"');
+    for c in lit.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+struct Ebnf<'a, F> {
+    rules: &'a Rules,
+    inline: F,
+}
+
+impl<F: Fn(u32) -> bool> Ebnf<'_, F> {
+    fn should_inline(&self, id: u32) -> bool {
+        self.rules.contains_key(&id) && (self.inline)(id)
+    }
+
+    /// `stack` holds the rules being printed, so that a cycle of single-use rules can't be
+    /// inlined forever.
+    fn body(&self, rule: &Rule, stack: &mut Vec<u32>) -> String {
+        match rule {
+            Rule::Alt(alts) => alts.iter().map(|seq| self.seq(seq, stack)).join(" | "),
+            rule => self.term(rule, stack),
+        }
+    }
+
+    fn seq(&self, seq: &[Rule], stack: &mut Vec<u32>) -> String {
+        seq.iter().map(|r| self.term(r, stack)).join(", ")
+    }
+
+    fn term(&self, rule: &Rule, stack: &mut Vec<u32>) -> String {
+        match rule {
+            Rule::Ref(id) if self.should_inline(*id) && !stack.contains(id) => {
+                stack.push(*id);
+                let inner = &self.rules[id];
+                let body = match inner {
+                    Rule::Alt(_) => format!("( {} )", self.body(inner, stack)),
+                    _ => self.body(inner, stack),
+                };
+                stack.pop();
+                body
+            }
+            Rule::Ref(id) => format!("r{id}"),
+            Rule::Lit(lit) => quote(lit),
+            Rule::Seq(seq) => self.seq(seq, stack),
+            Rule::Alt(_) => format!("( {} )", self.body(rule, stack)),
+        }
+    }
+}
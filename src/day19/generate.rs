@@ -0,0 +1,215 @@
+use std::collections::HashMap;
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use super::{Rule, Rules};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum GenerateError {
+    #[error("rule 0 can't generate any message")]
+    Unproductive,
+}
+
+/// Enumerates up to `limit` distinct messages accepted by rule 0, following rule references at
+/// most `max_depth` levels deep. The result is sorted.
+pub fn enumerate(rules: &Rules, max_depth: usize, limit: usize) -> Vec<String> {
+    let mut messages = expand(rules, &Rule::Ref(0), max_depth, limit);
+    messages.sort();
+    messages.dedup();
+    messages
+}
+
+fn expand(rules: &Rules, rule: &Rule, depth: usize, limit: usize) -> Vec<String> {
+    match rule {
+        Rule::Ref(id) => match rules.get(id) {
+            Some(rule) if depth > 0 => expand(rules, rule, depth - 1, limit),
+            _ => Vec::new(),
+        },
+        Rule::Lit(lit) => vec![lit.clone()],
+        Rule::Seq(seq) => expand_seq(rules, seq, depth, limit),
+        Rule::Alt(alts) => alts
+            .iter()
+            .flat_map(|seq| expand_seq(rules, seq, depth, limit))
+            .unique()
+            .take(limit)
+            .collect(),
+    }
+}
+
+fn expand_seq(rules: &Rules, seq: &[Rule], depth: usize, limit: usize) -> Vec<String> {
+    let mut prefixes = vec![String::new()];
+    for rule in seq {
+        let suffixes = expand(rules, rule, depth, limit);
+        prefixes = prefixes
+            .iter()
+            .cartesian_product(&suffixes)
+            .map(|(p, s)| format!("{p}{s}"))
+            .take(limit)
+            .collect();
+        if prefixes.is_empty() {
+            break;
+        }
+    }
+    prefixes
+}
+
+/// Randomly samples messages accepted by rule 0.
+///
+/// Alternatives are picked uniformly until `max_depth` rule references deep, from there on the
+/// alternative with the shortest derivation is taken so that recursive rules terminate.
+pub struct Sampler<'a> {
+    rules: &'a Rules,
+    /// number of rule references needed to derive the shortest message of each rule
+    heights: HashMap<u32, usize>,
+    max_depth: usize,
+    rng: Rng,
+}
+
+impl<'a> Sampler<'a> {
+    pub fn new(rules: &'a Rules, max_depth: usize, seed: u64) -> Result<Self, GenerateError> {
+        let mut heights = HashMap::new();
+        loop {
+            let mut changed = false;
+            for (&id, rule) in rules {
+                if let Some(h) = height(rule, &heights) {
+                    if heights.get(&id).is_none_or(|&old| h < old) {
+                        heights.insert(id, h);
+                        changed = true;
+                    }
+                }
+            }
+            if !changed {
+                break;
+            }
+        }
+
+        if !heights.contains_key(&0) {
+            return Err(GenerateError::Unproductive);
+        }
+        Ok(Self {
+            rules,
+            heights,
+            max_depth,
+            rng: Rng::new(seed),
+        })
+    }
+
+    pub fn sample(&mut self) -> String {
+        let mut out = String::new();
+        self.sample_rule(&Rule::Ref(0), 0, &mut out);
+        out
+    }
+
+    /// Applies a random single character substitution, insertion or deletion to `message`,
+    /// using characters that appear in the rules' literals.
+    pub fn mutate(&mut self, message: &str) -> String {
+        let alphabet: Vec<char> = self
+            .rules
+            .values()
+            .flat_map(literals)
+            .flat_map(str::chars)
+            .unique()
+            .collect();
+        if alphabet.is_empty() {
+            return message.to_owned();
+        }
+        let mut chars: Vec<char> = message.chars().collect();
+        let c = alphabet[self.rng.below(alphabet.len())];
+
+        match self.rng.below(3) {
+            0 if !chars.is_empty() => {
+                let i = self.rng.below(chars.len());
+                chars[i] = c;
+            }
+            1 if !chars.is_empty() => {
+                let i = self.rng.below(chars.len());
+                chars.remove(i);
+            }
+            _ => {
+                let i = self.rng.below(chars.len() + 1);
+                chars.insert(i, c);
+            }
+        }
+        chars.into_iter().collect()
+    }
+
+    fn sample_rule(&mut self, rule: &Rule, depth: usize, out: &mut String) {
+        match rule {
+            Rule::Ref(id) => {
+                let rules = self.rules;
+                self.sample_rule(&rules[id], depth + 1, out);
+            }
+            Rule::Lit(lit) => out.push_str(lit),
+            Rule::Seq(seq) => {
+                for rule in seq {
+                    self.sample_rule(rule, depth, out);
+                }
+            }
+            Rule::Alt(alts) => {
+                let heights: Vec<_> = alts
+                    .iter()
+                    .map(|seq| seq_height(seq, &self.heights))
+                    .collect();
+                let candidates: Vec<usize> = if depth >= self.max_depth {
+                    let min = heights.iter().flatten().min();
+                    (0..alts.len())
+                        .filter(|&i| heights[i].as_ref() == min)
+                        .collect()
+                } else {
+                    (0..alts.len()).filter(|&i| heights[i].is_some()).collect()
+                };
+                let pick = candidates[self.rng.below(candidates.len())];
+                for rule in &alts[pick] {
+                    self.sample_rule(rule, depth, out);
+                }
+            }
+        }
+    }
+}
+
+fn literals(rule: &Rule) -> Vec<&str> {
+    match rule {
+        Rule::Ref(_) => Vec::new(),
+        Rule::Lit(lit) => vec![lit.as_str()],
+        Rule::Seq(seq) => seq.iter().flat_map(literals).collect(),
+        Rule::Alt(alts) => alts.iter().flatten().flat_map(literals).collect(),
+    }
+}
+
+fn height(rule: &Rule, heights: &HashMap<u32, usize>) -> Option<usize> {
+    match rule {
+        Rule::Ref(id) => heights.get(id).map(|h| h + 1),
+        Rule::Lit(_) => Some(0),
+        Rule::Seq(seq) => seq_height(seq, heights),
+        Rule::Alt(alts) => alts.iter().filter_map(|seq| seq_height(seq, heights)).min(),
+    }
+}
+
+fn seq_height(seq: &[Rule], heights: &HashMap<u32, usize>) -> Option<usize> {
+    seq.iter()
+        .map(|r| height(r, heights))
+        .try_fold(0, |max, h| h.map(|h| max.max(h)))
+}
+
+/// Small xorshift64* generator, good enough for picking alternatives.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // the state must never be zero
+        Self((seed ^ 0x9E37_79B9_7F4A_7C15).max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+
+    /// Returns a number in `0..n`.
+    pub fn below(&mut self, n: usize) -> usize {
+        (self.next_u64() % n as u64) as usize
+    }
+}
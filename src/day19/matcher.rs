@@ -0,0 +1,340 @@
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    fmt,
+    ops::Range,
+};
+
+use thiserror::Error;
+
+use super::{Rule, Rules};
+
+#[derive(Debug, Error, PartialEq)]
+pub enum MatchError {
+    #[error("rule {0} is referenced but never defined")]
+    Undefined(u32),
+}
+
+/// Matches messages against the rules, rooted at rule 0.
+///
+/// Every rule is evaluated to the set of positions where a match starting at a given position
+/// can end. Recursive rules (left or right) are handled by memoizing those sets per
+/// `(rule, position)` and re-evaluating until none of them grows any more.
+pub struct Matcher<'a> {
+    rules: &'a Rules,
+}
+
+impl<'a> Matcher<'a> {
+    pub fn new(rules: &'a Rules) -> Self {
+        Self { rules }
+    }
+
+    pub fn matches(&self, message: &str) -> Result<bool, MatchError> {
+        let ends = self.ends(0, message)?;
+        Ok(ends.contains(&message.len()))
+    }
+
+    /// Returns every position at which a match of rule `id` starting at the beginning of
+    /// `message` can end.
+    pub fn ends(&self, id: u32, message: &str) -> Result<Vec<usize>, MatchError> {
+        Search::new(self.rules, message).solve(&Rule::Ref(id), 0)
+    }
+
+    /// Explains how `message` is matched by rule 0: the derivation tree if it matches, or the
+    /// furthest position any rule got to along with the literals expected there if it doesn't.
+    pub fn trace(&self, message: &str) -> Result<Trace, MatchError> {
+        let mut search = Search::new(self.rules, message);
+        let ends = search.solve(&Rule::Ref(0), 0)?;
+
+        if ends.contains(&message.len()) {
+            let mut guard = HashSet::new();
+            if let Some(tree) = search.derive_ref(0, 0, message.len(), &mut guard)? {
+                return Ok(Trace::Match(tree));
+            }
+        }
+
+        // a match of rule 0 that stops short of the end is further along than any literal that
+        // failed before it
+        match ends.iter().max() {
+            Some(&end) if end > search.furthest || search.expected.is_empty() => {
+                Ok(Trace::Failure {
+                    position: end,
+                    expected: Vec::new(),
+                })
+            }
+            _ => Ok(Trace::Failure {
+                position: search.furthest,
+                expected: search.expected.into_iter().collect(),
+            }),
+        }
+    }
+}
+
+/// Result of [`Matcher::trace`].
+#[derive(Debug, PartialEq)]
+pub enum Trace {
+    Match(Tree),
+    /// An empty `expected` list means the message should have ended at `position`.
+    Failure {
+        position: usize,
+        expected: Vec<String>,
+    },
+}
+
+/// Derivation of a matched message, spans are byte ranges into the message.
+#[derive(Debug, PartialEq)]
+pub enum Tree {
+    Rule {
+        id: u32,
+        /// index of the matching alternative, if the rule has more than one
+        alt: Option<usize>,
+        span: Range<usize>,
+        children: Vec<Tree>,
+    },
+    Lit {
+        text: String,
+        span: Range<usize>,
+    },
+}
+
+impl Tree {
+    fn fmt_indented(&self, f: &mut fmt::Formatter, indent: usize) -> fmt::Result {
+        match self {
+            Tree::Rule {
+                id,
+                alt,
+                span,
+                children,
+            } => {
+                write!(f, "{:indent$}{id} [{}..{}]", "", span.start, span.end)?;
+                if let Some(alt) = alt {
+                    write!(f, " alt {alt}")?;
+                }
+                writeln!(f)?;
+                for child in children {
+                    child.fmt_indented(f, indent + 2)?;
+                }
+                Ok(())
+            }
+            Tree::Lit { text, span } => {
+                writeln!(f, "{:indent$}{text:?} [{}..{}]", "", span.start, span.end)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Tree {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_indented(f, 0)
+    }
+}
+
+struct Search<'a> {
+    rules: &'a Rules,
+    msg: &'a [u8],
+    /// current approximation of the end positions for each `(rule, position)`
+    memo: HashMap<(u32, usize), Vec<usize>>,
+    /// entries evaluated during the current round
+    visited: HashSet<(u32, usize)>,
+    /// entries that are currently being evaluated further up the call stack
+    active: HashSet<(u32, usize)>,
+    stale: bool,
+    changed: bool,
+    /// furthest position at which a literal failed to match, and the literals tried there
+    furthest: usize,
+    expected: BTreeSet<String>,
+}
+
+impl<'a> Search<'a> {
+    fn new(rules: &'a Rules, message: &'a str) -> Self {
+        Self {
+            rules,
+            msg: message.as_bytes(),
+            memo: HashMap::new(),
+            visited: HashSet::new(),
+            active: HashSet::new(),
+            stale: false,
+            changed: false,
+            furthest: 0,
+            expected: BTreeSet::new(),
+        }
+    }
+
+    /// Evaluates `rule` at `pos`, repeating until the memoized results reach a fixpoint.
+    fn solve(&mut self, rule: &Rule, pos: usize) -> Result<Vec<usize>, MatchError> {
+        loop {
+            self.visited.clear();
+            self.stale = false;
+            self.changed = false;
+            let ends = self.ends(rule, pos)?;
+            // a fixpoint is reached once no rule read an unfinished result, or none of the
+            // unfinished results changed since the last round
+            if !self.stale || !self.changed {
+                return Ok(ends);
+            }
+        }
+    }
+
+    fn ends(&mut self, rule: &Rule, pos: usize) -> Result<Vec<usize>, MatchError> {
+        match rule {
+            Rule::Ref(id) => self.ref_ends(*id, pos),
+            Rule::Lit(lit) => {
+                if self.msg[pos..].starts_with(lit.as_bytes()) {
+                    return Ok(vec![pos + lit.len()]);
+                }
+                if pos > self.furthest {
+                    self.furthest = pos;
+                    self.expected.clear();
+                }
+                if pos == self.furthest {
+                    self.expected.insert(lit.clone());
+                }
+                Ok(vec![])
+            }
+            Rule::Seq(seq) => self.seq_ends(seq, pos),
+            Rule::Alt(alts) => {
+                let mut ends = Vec::new();
+                for seq in alts {
+                    ends.extend(self.seq_ends(seq, pos)?);
+                }
+                ends.sort_unstable();
+                ends.dedup();
+                Ok(ends)
+            }
+        }
+    }
+
+    fn ref_ends(&mut self, id: u32, pos: usize) -> Result<Vec<usize>, MatchError> {
+        let key = (id, pos);
+        if self.active.contains(&key) {
+            self.stale = true;
+            return Ok(self.memo.get(&key).cloned().unwrap_or_default());
+        }
+        if self.visited.contains(&key) {
+            return Ok(self.memo[&key].clone());
+        }
+
+        let rules = self.rules;
+        let rule = rules.get(&id).ok_or(MatchError::Undefined(id))?;
+        self.visited.insert(key);
+        self.active.insert(key);
+        let ends = self.ends(rule, pos);
+        self.active.remove(&key);
+        let ends = ends?;
+
+        if self.memo.get(&key) != Some(&ends) {
+            self.changed = true;
+            self.memo.insert(key, ends.clone());
+        }
+        Ok(ends)
+    }
+
+    fn seq_ends(&mut self, seq: &[Rule], pos: usize) -> Result<Vec<usize>, MatchError> {
+        let mut positions = vec![pos];
+        for rule in seq {
+            let mut next = Vec::new();
+            for p in positions {
+                next.extend(self.ends(rule, p)?);
+            }
+            next.sort_unstable();
+            next.dedup();
+            positions = next;
+            if positions.is_empty() {
+                break;
+            }
+        }
+        Ok(positions)
+    }
+
+    /// Builds a derivation of rule `id` spanning exactly `pos..end`. `guard` holds the
+    /// derivations in progress, so that cycles through rules matching the empty string are cut.
+    fn derive_ref(
+        &mut self,
+        id: u32,
+        pos: usize,
+        end: usize,
+        guard: &mut HashSet<(u32, usize, usize)>,
+    ) -> Result<Option<Tree>, MatchError> {
+        if !guard.insert((id, pos, end)) {
+            return Ok(None);
+        }
+
+        let rules = self.rules;
+        let rule = rules.get(&id).ok_or(MatchError::Undefined(id))?;
+        let mut tree = None;
+        match rule {
+            Rule::Alt(alts) => {
+                for (i, seq) in alts.iter().enumerate() {
+                    if let Some(children) = self.derive_seq(seq, pos, end, guard)? {
+                        tree = Some((Some(i), children));
+                        break;
+                    }
+                }
+            }
+            rule => {
+                let seq = std::slice::from_ref(rule);
+                tree = self.derive_seq(seq, pos, end, guard)?.map(|c| (None, c));
+            }
+        }
+
+        guard.remove(&(id, pos, end));
+        Ok(tree.map(|(alt, children)| Tree::Rule {
+            id,
+            alt,
+            span: pos..end,
+            children,
+        }))
+    }
+
+    fn derive_seq(
+        &mut self,
+        seq: &[Rule],
+        pos: usize,
+        end: usize,
+        guard: &mut HashSet<(u32, usize, usize)>,
+    ) -> Result<Option<Vec<Tree>>, MatchError> {
+        let Some((first, rest)) = seq.split_first() else {
+            return Ok((pos == end).then(Vec::new));
+        };
+
+        for mid in self.solve(first, pos)? {
+            if mid > end {
+                continue;
+            }
+            let Some(mut trees) = self.derive_one(first, pos, mid, guard)? else {
+                continue;
+            };
+            if let Some(rest) = self.derive_seq(rest, mid, end, guard)? {
+                trees.extend(rest);
+                return Ok(Some(trees));
+            }
+        }
+        Ok(None)
+    }
+
+    fn derive_one(
+        &mut self,
+        rule: &Rule,
+        pos: usize,
+        end: usize,
+        guard: &mut HashSet<(u32, usize, usize)>,
+    ) -> Result<Option<Vec<Tree>>, MatchError> {
+        match rule {
+            Rule::Ref(id) => Ok(self.derive_ref(*id, pos, end, guard)?.map(|t| vec![t])),
+            Rule::Lit(lit) => Ok((pos + lit.len() == end).then(|| {
+                vec![Tree::Lit {
+                    text: lit.clone(),
+                    span: pos..end,
+                }]
+            })),
+            Rule::Seq(seq) => self.derive_seq(seq, pos, end, guard),
+            Rule::Alt(alts) => {
+                for seq in alts {
+                    if let Some(trees) = self.derive_seq(seq, pos, end, guard)? {
+                        return Ok(Some(trees));
+                    }
+                }
+                Ok(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,629 @@
+use crate::{parse_arg, Solution};
+use anyhow::{anyhow, bail, Context};
+use std::{
+    collections::HashMap,
+    env::args,
+    fs,
+    num::ParseIntError,
+    time::{Instant, SystemTime, UNIX_EPOCH},
+};
+use thiserror::Error;
+
+mod dfa;
+mod export;
+mod generate;
+mod matcher;
+mod validate;
+
+use dfa::{CompileError, Dfa};
+use generate::Sampler;
+use itertools::Itertools;
+use matcher::{Matcher, Trace};
+use validate::validate;
+
+/// Replacements for rules 8 and 11 that turn the grammar recursive in part 2.
+const PART2_PATCH: &str = "8: 42 | 42 8\n11: 42 31 | 42 11 31";
+
+/// Entry point of the `day19` binary.
+pub fn main() -> anyhow::Result<()> {
+    let mut args = args().skip(1);
+    let Some(path) = args.next() else {
+        bail!("please provide the path to input file")
+    };
+    let mut puzzle = Day19::parse(&fs::read_to_string(path)?)?;
+
+    // `--patch "<id>: <rule>"` replaces rules before anything is matched
+    let mut mode = Mode::Solve;
+    let mut collapse = false;
+    let mut depth = 8;
+    let mut seed = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--patch" => {
+                let patch = args
+                    .next()
+                    .ok_or_else(|| anyhow!("`--patch` expects a rule"))?;
+                patch_rules(&mut puzzle.rules, &patch)?;
+            }
+            "--bench" => mode = Mode::Bench,
+            "--validate" => mode = Mode::Validate,
+            "--export" => {
+                mode = match args.next().as_deref() {
+                    Some("ebnf") => Mode::Ebnf,
+                    Some("dot") => Mode::Dot,
+                    _ => bail!("`--export` expects either `ebnf` or `dot`"),
+                }
+            }
+            "--collapse" => collapse = true,
+            "--generate" => mode = Mode::Generate(parse_arg(args.next(), "--generate")?),
+            "--fuzz" => mode = Mode::Fuzz(parse_arg(args.next(), "--fuzz")?),
+            "--trace" => {
+                let message = args
+                    .next()
+                    .ok_or_else(|| anyhow!("`--trace` expects a message"))?;
+                mode = Mode::Trace(message);
+            }
+            "--enumerate" => mode = Mode::Enumerate(parse_arg(args.next(), "--enumerate")?),
+            "--depth" => depth = parse_arg(args.next(), "--depth")?,
+            "--seed" => seed = Some(parse_arg(args.next(), "--seed")?),
+            x => bail!("unexpected argument: `{x}`"),
+        }
+    }
+
+    match mode {
+        Mode::Solve => {
+            let ans1 = Day19::part1(&puzzle)?;
+            println!("Part1: {ans1}");
+
+            let ans2 = Day19::part2(&puzzle)?;
+            println!("Part2: {ans2}");
+        }
+        Mode::Bench => run_bench(&puzzle)?,
+        Mode::Validate => {
+            let issues = validate(&puzzle.rules);
+            for issue in &issues {
+                println!("{issue}");
+            }
+            if !issues.is_empty() {
+                bail!("found {} issue(s) in the rules", issues.len());
+            }
+            println!("the rules are valid");
+        }
+        Mode::Ebnf => print!("{}", export::to_ebnf(&puzzle.rules, collapse)),
+        Mode::Dot => print!("{}", export::to_dot(&puzzle.rules)),
+        Mode::Generate(n) => {
+            let mut sampler = Sampler::new(&puzzle.rules, depth, seed_or_now(seed)?)?;
+            for _ in 0..n {
+                println!("{}", sampler.sample());
+            }
+        }
+        Mode::Fuzz(n) => {
+            fuzz(&puzzle.rules, n, depth, seed_or_now(seed)?)?;
+        }
+        Mode::Trace(message) => match Matcher::new(&puzzle.rules).trace(&message)? {
+            Trace::Match(tree) => print!("{tree}"),
+            Trace::Failure { position, expected } => {
+                println!("{message}");
+                let col = message[..position].chars().count();
+                let expected = if expected.is_empty() {
+                    "the end of the message".to_owned()
+                } else {
+                    expected.iter().map(|e| format!("{e:?}")).join(" or ")
+                };
+                println!("{:col$}^ expected {expected}", "");
+                bail!("the message doesn't match rule 0");
+            }
+        },
+        Mode::Enumerate(n) => {
+            for message in generate::enumerate(&puzzle.rules, depth, n) {
+                println!("{message}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// The seed given with `--seed`, or one taken from the clock.
+fn seed_or_now(seed: Option<u64>) -> anyhow::Result<u64> {
+    match seed {
+        Some(seed) => Ok(seed),
+        None => Ok(SystemTime::now().duration_since(UNIX_EPOCH)?.as_nanos() as u64),
+    }
+}
+
+enum Mode {
+    Solve,
+    Bench,
+    Validate,
+    Ebnf,
+    Dot,
+    Generate(usize),
+    Fuzz(usize),
+    Enumerate(usize),
+    Trace(String),
+}
+
+pub struct Day19;
+
+pub struct Puzzle {
+    rules: Rules,
+    messages: Vec<String>,
+}
+
+impl Solution for Day19 {
+    type Input = Puzzle;
+    type Output = usize;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input> {
+        let (rule_str, messages) = input.split_once("\n\n").ok_or_else(|| {
+            anyhow!("expected the input to consist of rules and messages section")
+        })?;
+
+        let mut rules = Rules::new();
+        patch_rules(&mut rules, rule_str)?;
+
+        let messages = messages.lines().map(String::from).collect();
+        Ok(Puzzle { rules, messages })
+    }
+
+    fn part1(puzzle: &Self::Input) -> anyhow::Result<usize> {
+        count_matching(&puzzle.rules, &puzzle.messages)
+    }
+
+    fn part2(puzzle: &Self::Input) -> anyhow::Result<usize> {
+        let mut rules = puzzle.rules.clone();
+        patch_rules(&mut rules, PART2_PATCH)?;
+        count_matching(&rules, &puzzle.messages)
+    }
+}
+
+/// Parses `id: rule` lines and inserts them into `rules`, replacing existing rules with the
+/// same id.
+fn patch_rules(rules: &mut Rules, rule_str: &str) -> anyhow::Result<()> {
+    for (n, line) in rule_str.lines().enumerate() {
+        let (id, rule) = parse_rule(line).with_context(|| format!("on line {}", n + 1))?;
+        rules.insert(id, rule);
+    }
+    Ok(())
+}
+
+/// Counts the messages matching rule 0, going through a [`Dfa`] whenever the grammar allows it.
+fn count_matching(rules: &Rules, messages: &[String]) -> anyhow::Result<usize> {
+    match Dfa::compile(rules) {
+        Ok(dfa) => Ok(messages.iter().filter(|m| dfa.matches(m)).count()),
+        Err(CompileError::Recursive(_)) => count_with_matcher(rules, messages),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn count_with_matcher(rules: &Rules, messages: &[String]) -> anyhow::Result<usize> {
+    let matcher = Matcher::new(rules);
+    let mut count = 0;
+    for message in messages {
+        if matcher.matches(message)? {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// Checks that the matcher accepts `n` sampled messages and reports how many single character
+/// mutations of them still match.
+fn fuzz(rules: &Rules, n: usize, depth: usize, seed: u64) -> anyhow::Result<()> {
+    let matcher = Matcher::new(rules);
+    let mut sampler = Sampler::new(rules, depth, seed)?;
+    let mut mutants_matched = 0;
+    for _ in 0..n {
+        let message = sampler.sample();
+        if !matcher.matches(&message)? {
+            bail!("generated message `{message}` is rejected by the matcher (seed {seed})");
+        }
+        if matcher.matches(&sampler.mutate(&message))? {
+            mutants_matched += 1;
+        }
+    }
+    println!("all {n} generated messages matched");
+    println!("{mutants_matched} of {n} mutated messages matched as well");
+    Ok(())
+}
+
+fn run_bench(puzzle: &Puzzle) -> anyhow::Result<()> {
+    const ROUNDS: u32 = 10;
+
+    let start = Instant::now();
+    let dfa = Dfa::compile(&puzzle.rules)?;
+    println!(
+        "DFA compiled to {} states in {:.2?}",
+        dfa.state_count(),
+        start.elapsed()
+    );
+
+    let start = Instant::now();
+    let mut dfa_count = 0;
+    for _ in 0..ROUNDS {
+        dfa_count = puzzle.messages.iter().filter(|m| dfa.matches(m)).count();
+    }
+    let dfa_time = start.elapsed() / ROUNDS;
+
+    let start = Instant::now();
+    let mut matcher_count = 0;
+    for _ in 0..ROUNDS {
+        matcher_count = count_with_matcher(&puzzle.rules, &puzzle.messages)?;
+    }
+    let matcher_time = start.elapsed() / ROUNDS;
+
+    if dfa_count != matcher_count {
+        bail!("the DFA matched {dfa_count} messages but the matcher {matcher_count}");
+    }
+    println!("DFA:     {dfa_count} matches in {dfa_time:.2?} per round");
+    println!("Matcher: {matcher_count} matches in {matcher_time:.2?} per round");
+    Ok(())
+}
+
+type Rules = HashMap<u32, Rule>;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Rule {
+    Ref(u32),
+    Lit(String),
+    Seq(Vec<Rule>),
+    Alt(Vec<Vec<Rule>>),
+}
+
+#[derive(Debug, Error, PartialEq)]
+enum ParseRuleError {
+    #[error("the rule entry must have the format of `id: rule`, found `{0}`")]
+    Format(String),
+    #[error("invalid rule id `{0}`")]
+    Id(String),
+    #[error("rule {id}, column {col}: invalid index")]
+    Index {
+        id: u32,
+        col: usize,
+        source: ParseIntError,
+    },
+    #[error("rule {id}, column {col}: unterminated literal")]
+    UnterminatedLit { id: u32, col: usize },
+    #[error("rule {id}, column {col}: unknown escape sequence `\\{c}`")]
+    Escape { id: u32, col: usize, c: char },
+    #[error("rule {id}, column {col}: empty alternative")]
+    EmptyAlt { id: u32, col: usize },
+    #[error("rule {id}, column {col}: empty rule")]
+    EmptyRule { id: u32, col: usize },
+}
+
+/// Parses a single `id: rule` entry.
+///
+/// A rule is a list of alternatives separated by `|`, each a sequence of rule ids and quoted
+/// literals. Literals may contain `\"`, `\\`, `\n` and `\t` escapes.
+fn parse_rule(line: &str) -> Result<(u32, Rule), ParseRuleError> {
+    let (id, body) = line
+        .split_once(':')
+        .ok_or_else(|| ParseRuleError::Format(line.to_owned()))?;
+    let id = id
+        .trim()
+        .parse()
+        .map_err(|_| ParseRuleError::Id(id.to_owned()))?;
+
+    let offset = id_len(line);
+    let col = |i: usize| line[..offset + i].chars().count() + 1;
+
+    let mut alts = vec![Vec::new()];
+    let mut chars = body.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {}
+            '|' => {
+                if alts.last().is_some_and(Vec::is_empty) {
+                    return Err(ParseRuleError::EmptyAlt { id, col: col(i) });
+                }
+                alts.push(Vec::new());
+            }
+            '"' => {
+                let mut lit = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((j, '\\')) => match chars.next() {
+                            Some((_, '"')) => lit.push('"'),
+                            Some((_, '\\')) => lit.push('\\'),
+                            Some((_, 'n')) => lit.push('\n'),
+                            Some((_, 't')) => lit.push('\t'),
+                            Some((_, c)) => {
+                                return Err(ParseRuleError::Escape { id, col: col(j), c })
+                            }
+                            None => {
+                                return Err(ParseRuleError::UnterminatedLit { id, col: col(i) })
+                            }
+                        },
+                        Some((_, c)) => lit.push(c),
+                        None => return Err(ParseRuleError::UnterminatedLit { id, col: col(i) }),
+                    }
+                }
+                alts.last_mut().unwrap().push(Rule::Lit(lit));
+            }
+            _ => {
+                let mut end = i + c.len_utf8();
+                while let Some(&(j, c)) = chars.peek() {
+                    if c.is_whitespace() || c == '|' || c == '"' {
+                        break;
+                    }
+                    end = j + c.len_utf8();
+                    chars.next();
+                }
+                let index = body[i..end]
+                    .parse()
+                    .map_err(|source| ParseRuleError::Index {
+                        id,
+                        col: col(i),
+                        source,
+                    })?;
+                alts.last_mut().unwrap().push(Rule::Ref(index));
+            }
+        }
+    }
+
+    if alts.last().is_some_and(Vec::is_empty) {
+        let col = col(body.len());
+        return Err(if alts.len() > 1 {
+            ParseRuleError::EmptyAlt { id, col }
+        } else {
+            ParseRuleError::EmptyRule { id, col }
+        });
+    }
+
+    let rule = if alts.len() > 1 {
+        Rule::Alt(alts)
+    } else {
+        let mut seq = alts.pop().unwrap();
+        if seq.len() == 1 {
+            seq.pop().unwrap()
+        } else {
+            Rule::Seq(seq)
+        }
+    };
+    Ok((id, rule))
+}
+
+/// Byte length of the `id:` prefix of a rule entry.
+fn id_len(line: &str) -> usize {
+    line.find(':').map_or(0, |i| i + 1)
+}
+
+#[test]
+fn example() {
+    let input = include_str!("test_input.txt");
+    let puzzle = Day19::parse(input).unwrap();
+    assert_eq!(Day19::part1(&puzzle).unwrap(), 2);
+}
+
+#[test]
+fn recursive_rules() {
+    let input = "0: 1 2\n1: 1 3 | 3\n2: 4 2 | 4\n3: \"a\"\n4: \"b\"\n\nab\naaabbb\nba\naaa";
+    let puzzle = Day19::parse(input).unwrap();
+    assert_eq!(Day19::part1(&puzzle).unwrap(), 2);
+}
+
+#[test]
+fn dfa_agrees_with_matcher() {
+    let input = include_str!("test_input.txt");
+    let puzzle = Day19::parse(input).unwrap();
+    let dfa = Dfa::compile(&puzzle.rules).unwrap();
+    let matcher = Matcher::new(&puzzle.rules);
+    for message in &puzzle.messages {
+        assert_eq!(dfa.matches(message), matcher.matches(message).unwrap());
+    }
+}
+
+#[test]
+fn parse_rules() {
+    assert_eq!(parse_rule("4: \"a\""), Ok((4, Rule::Lit("a".to_owned()))));
+    assert_eq!(parse_rule("8: 42"), Ok((8, Rule::Ref(42))));
+    assert_eq!(
+        parse_rule("1: 2 3 | 3 2 | 4"),
+        Ok((
+            1,
+            Rule::Alt(vec![
+                vec![Rule::Ref(2), Rule::Ref(3)],
+                vec![Rule::Ref(3), Rule::Ref(2)],
+                vec![Rule::Ref(4)],
+            ])
+        ))
+    );
+    assert_eq!(
+        parse_rule(r#"7: "ä\"b\\" 3"#),
+        Ok((
+            7,
+            Rule::Seq(vec![Rule::Lit("ä\"b\\".to_owned()), Rule::Ref(3)])
+        ))
+    );
+}
+
+#[test]
+fn parse_rule_errors() {
+    assert!(matches!(
+        parse_rule("3: 4 x5"),
+        Err(ParseRuleError::Index { id: 3, col: 6, .. })
+    ));
+    assert_eq!(
+        parse_rule("3: \"ab"),
+        Err(ParseRuleError::UnterminatedLit { id: 3, col: 4 })
+    );
+    assert_eq!(
+        parse_rule("3: \"a\\qb\""),
+        Err(ParseRuleError::Escape {
+            id: 3,
+            col: 6,
+            c: 'q'
+        })
+    );
+    assert_eq!(
+        parse_rule("3: 1 | | 2"),
+        Err(ParseRuleError::EmptyAlt { id: 3, col: 8 })
+    );
+    assert_eq!(
+        parse_rule("3: 1 |"),
+        Err(ParseRuleError::EmptyAlt { id: 3, col: 7 })
+    );
+    assert_eq!(
+        parse_rule("3: | 2"),
+        Err(ParseRuleError::EmptyAlt { id: 3, col: 4 })
+    );
+    assert_eq!(
+        parse_rule("1: "),
+        Err(ParseRuleError::EmptyRule { id: 1, col: 4 })
+    );
+    assert_eq!(
+        parse_rule("1:"),
+        Err(ParseRuleError::EmptyRule { id: 1, col: 3 })
+    );
+    assert_eq!(parse_rule("x: 1"), Err(ParseRuleError::Id("x".to_owned())));
+}
+
+#[test]
+fn validation() {
+    use validate::Issue;
+
+    let mut rules = Rules::new();
+    patch_rules(
+        &mut rules,
+        "0: 1 2\n1: 1 \"a\" | 3\n2: 5 | \"b\"\n4: \"c\"\n5: 5 \"d\"",
+    )
+    .unwrap();
+    assert_eq!(
+        validate(&rules),
+        vec![
+            Issue::Undefined {
+                rule: 1,
+                missing: 3
+            },
+            Issue::Unreachable(4),
+            Issue::LeftRecursive(vec![1]),
+            Issue::LeftRecursive(vec![5]),
+            Issue::Empty(0),
+            Issue::Empty(1),
+            Issue::Empty(5),
+        ]
+    );
+
+    let input = include_str!("test_input.txt");
+    let puzzle = Day19::parse(input).unwrap();
+    assert_eq!(validate(&puzzle.rules), vec![]);
+}
+
+#[test]
+fn export() {
+    let mut rules = Rules::new();
+    patch_rules(&mut rules, "0: 1 2\n1: \"a\" | 2 1\n2: \"\\\"\"").unwrap();
+    assert_eq!(
+        export::to_ebnf(&rules, false),
+        "r0 = r1, r2 ;\nr1 = \"a\" | r2, r1 ;\nr2 = \"\\\"\" ;\n"
+    );
+    assert_eq!(
+        export::to_ebnf(&rules, true),
+        "r0 = r1, r2 ;\nr1 = \"a\" | r2, r1 ;\nr2 = \"\\\"\" ;\n"
+    );
+    patch_rules(&mut rules, "0: 1\n1: \"a\" | 2\n2: \"b\" 3\n3: \"c\"").unwrap();
+    assert_eq!(
+        export::to_ebnf(&rules, true),
+        "r0 = ( \"a\" | \"b\", \"c\" ) ;\n"
+    );
+    assert_eq!(
+        export::to_dot(&rules),
+        "digraph rules {\n    0 [label=\"0\"];\n    1 [label=\"1\"];\n    2 [label=\"2\"];\n    3 [label=\"3: \\\"c\\\"\"];\n    0 -> 1;\n    1 -> 2;\n    2 -> 3;\n}\n"
+    );
+
+    // rules only used by themselves or each other aren't reached from any production
+    let mut rules = Rules::new();
+    patch_rules(&mut rules, "0: 1\n1: \"a\"\n5: 5 \"d\" | \"e\"").unwrap();
+    assert_eq!(
+        export::to_ebnf(&rules, true),
+        "r0 = \"a\" ;\nr5 = r5, \"d\" | \"e\" ;\n"
+    );
+    patch_rules(&mut rules, "5: 6 \"d\" | \"e\"\n6: 5 \"f\"").unwrap();
+    assert_eq!(
+        export::to_ebnf(&rules, true),
+        "r0 = \"a\" ;\nr5 = r5, \"f\", \"d\" | \"e\" ;\n"
+    );
+    patch_rules(&mut rules, "0: 1 5\n5: 5 \"d\" | \"e\"").unwrap();
+    assert_eq!(
+        export::to_ebnf(&rules, true),
+        "r0 = \"a\", r5 ;\nr5 = r5, \"d\" | \"e\" ;\nr6 = r5, \"f\" ;\n"
+    );
+}
+
+#[test]
+fn generated_messages_match() {
+    let mut rules = Rules::new();
+    let grammar = "0: 8 11\n8: 42 | 42 8\n11: 42 31 | 42 11 31\n42: \"abb\" | \"baa\"\n31: \"aab\"";
+    patch_rules(&mut rules, grammar).unwrap();
+    let puzzle = Day19::parse(include_str!("test_input.txt")).unwrap();
+
+    for rules in [&rules, &puzzle.rules] {
+        let matcher = Matcher::new(rules);
+        let mut sampler = Sampler::new(rules, 6, 42).unwrap();
+        let mut mutants_matched = 0;
+        for _ in 0..200 {
+            let message = sampler.sample();
+            assert!(matcher.matches(&message).unwrap(), "{message}");
+            let mutant = sampler.mutate(&message);
+            if matcher.matches(&mutant).unwrap() {
+                mutants_matched += 1;
+            }
+        }
+        assert!(mutants_matched < 100, "{mutants_matched} mutants matched");
+
+        for message in generate::enumerate(rules, 6, 100) {
+            assert!(matcher.matches(&message).unwrap(), "{message}");
+        }
+    }
+
+    assert_eq!(generate::enumerate(&puzzle.rules, 10, 100).len(), 8);
+}
+
+#[test]
+fn trace() {
+    let puzzle = Day19::parse(include_str!("test_input.txt")).unwrap();
+    let matcher = Matcher::new(&puzzle.rules);
+
+    let Trace::Match(tree) = matcher.trace("ababbb").unwrap() else {
+        panic!("expected `ababbb` to match");
+    };
+    assert_eq!(
+        tree.to_string(),
+        "0 [0..6]
+  4 [0..1]
+    \"a\" [0..1]
+  1 [1..5] alt 1
+    3 [1..3] alt 1
+      5 [1..2]
+        \"b\" [1..2]
+      4 [2..3]
+        \"a\" [2..3]
+    2 [3..5] alt 1
+      5 [3..4]
+        \"b\" [3..4]
+      5 [4..5]
+        \"b\" [4..5]
+  5 [5..6]
+    \"b\" [5..6]
+"
+    );
+    assert!(matches!(tree, matcher::Tree::Rule { id: 0, .. }));
+
+    assert_eq!(
+        matcher.trace("aaaabbb").unwrap(),
+        Trace::Failure {
+            position: 6,
+            expected: vec![],
+        }
+    );
+    assert_eq!(
+        matcher.trace("bababa").unwrap(),
+        Trace::Failure {
+            position: 0,
+            expected: vec!["a".to_owned()],
+        }
+    );
+}
@@ -0,0 +1,223 @@
+use std::collections::{HashMap, HashSet};
+
+use itertools::Itertools;
+use thiserror::Error;
+
+use super::{Rule, Rules};
+
+/// Problems with a rule set that would otherwise only show up (if ever) while matching.
+#[derive(Debug, Error, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Issue {
+    #[error("rule 0 is not defined")]
+    MissingRoot,
+    #[error("rule {rule} references undefined rule {missing}")]
+    Undefined { rule: u32, missing: u32 },
+    #[error("rule {0} is unreachable from rule 0")]
+    Unreachable(u32),
+    #[error("left recursion among rules {}", .0.iter().join(", "))]
+    LeftRecursive(Vec<u32>),
+    #[error("rule {0} can't match any message")]
+    Empty(u32),
+}
+
+/// Checks `rules` for undefined references, rules unreachable from rule 0, left recursion and
+/// rules whose language is empty. Returns the issues found, sorted by kind and rule id.
+pub fn validate(rules: &Rules) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let ids = rules.keys().copied().sorted().collect_vec();
+
+    if !rules.contains_key(&0) {
+        issues.push(Issue::MissingRoot);
+    }
+
+    for &id in &ids {
+        let mut refs = Vec::new();
+        collect_refs(&rules[&id], &mut refs);
+        for missing in refs.into_iter().filter(|r| !rules.contains_key(r)).unique() {
+            issues.push(Issue::Undefined { rule: id, missing });
+        }
+    }
+
+    let reachable = reachable(rules);
+    for &id in &ids {
+        if !reachable.contains(&id) {
+            issues.push(Issue::Unreachable(id));
+        }
+    }
+
+    for cycle in left_recursive_cycles(rules) {
+        issues.push(Issue::LeftRecursive(cycle));
+    }
+
+    let productive = fixpoint(rules, productive);
+    for &id in &ids {
+        if !productive.contains(&id) {
+            issues.push(Issue::Empty(id));
+        }
+    }
+
+    issues.sort();
+    issues
+}
+
+fn collect_refs(rule: &Rule, refs: &mut Vec<u32>) {
+    match rule {
+        Rule::Ref(id) => refs.push(*id),
+        Rule::Lit(_) => {}
+        Rule::Seq(seq) => seq.iter().for_each(|r| collect_refs(r, refs)),
+        Rule::Alt(alts) => alts.iter().flatten().for_each(|r| collect_refs(r, refs)),
+    }
+}
+
+fn reachable(rules: &Rules) -> HashSet<u32> {
+    let mut seen = HashSet::new();
+    let mut stack = vec![0];
+    while let Some(id) = stack.pop() {
+        let Some(rule) = rules.get(&id) else {
+            continue;
+        };
+        if seen.insert(id) {
+            collect_refs(rule, &mut stack);
+        }
+    }
+    seen
+}
+
+/// Computes the least set of rule ids for which `holds` is true, given the ids known so far.
+fn fixpoint(rules: &Rules, holds: impl Fn(&Rule, &HashSet<u32>) -> bool) -> HashSet<u32> {
+    let mut known = HashSet::new();
+    loop {
+        let before = known.len();
+        for (&id, rule) in rules {
+            if !known.contains(&id) && holds(rule, &known) {
+                known.insert(id);
+            }
+        }
+        if known.len() == before {
+            return known;
+        }
+    }
+}
+
+/// Whether `rule` matches at least one string, given which rules are known to do so.
+fn productive(rule: &Rule, known: &HashSet<u32>) -> bool {
+    match rule {
+        Rule::Ref(id) => known.contains(id),
+        Rule::Lit(_) => true,
+        Rule::Seq(seq) => seq.iter().all(|r| productive(r, known)),
+        Rule::Alt(alts) => alts
+            .iter()
+            .any(|seq| seq.iter().all(|r| productive(r, known))),
+    }
+}
+
+/// Whether `rule` matches the empty string, given which rules are known to do so.
+fn nullable(rule: &Rule, known: &HashSet<u32>) -> bool {
+    match rule {
+        Rule::Ref(id) => known.contains(id),
+        Rule::Lit(lit) => lit.is_empty(),
+        Rule::Seq(seq) => seq.iter().all(|r| nullable(r, known)),
+        Rule::Alt(alts) => alts
+            .iter()
+            .any(|seq| seq.iter().all(|r| nullable(r, known))),
+    }
+}
+
+/// Collects the rules that can be entered without consuming any input first.
+fn leftmost_refs(rule: &Rule, nullable_ids: &HashSet<u32>, refs: &mut Vec<u32>) {
+    match rule {
+        Rule::Ref(id) => refs.push(*id),
+        Rule::Lit(_) => {}
+        Rule::Seq(seq) => leftmost_seq(seq, nullable_ids, refs),
+        Rule::Alt(alts) => alts
+            .iter()
+            .for_each(|seq| leftmost_seq(seq, nullable_ids, refs)),
+    }
+}
+
+fn leftmost_seq(seq: &[Rule], nullable_ids: &HashSet<u32>, refs: &mut Vec<u32>) {
+    for rule in seq {
+        leftmost_refs(rule, nullable_ids, refs);
+        if !nullable(rule, nullable_ids) {
+            break;
+        }
+    }
+}
+
+/// Finds the strongly connected components of the "can start with" graph that contain a cycle.
+fn left_recursive_cycles(rules: &Rules) -> Vec<Vec<u32>> {
+    let nullable_ids = fixpoint(rules, nullable);
+    let graph: HashMap<u32, Vec<u32>> = rules
+        .iter()
+        .map(|(&id, rule)| {
+            let mut refs = Vec::new();
+            leftmost_refs(rule, &nullable_ids, &mut refs);
+            refs.retain(|r| rules.contains_key(r));
+            (id, refs)
+        })
+        .collect();
+
+    let mut tarjan = Tarjan {
+        graph: &graph,
+        index: HashMap::new(),
+        low: HashMap::new(),
+        stack: Vec::new(),
+        on_stack: HashSet::new(),
+        components: Vec::new(),
+    };
+    for id in graph.keys().copied().sorted() {
+        if !tarjan.index.contains_key(&id) {
+            tarjan.visit(id);
+        }
+    }
+
+    tarjan
+        .components
+        .into_iter()
+        .filter(|c| c.len() > 1 || graph[&c[0]].contains(&c[0]))
+        .map(|c| c.into_iter().sorted().collect())
+        .sorted()
+        .collect()
+}
+
+struct Tarjan<'a> {
+    graph: &'a HashMap<u32, Vec<u32>>,
+    index: HashMap<u32, usize>,
+    low: HashMap<u32, usize>,
+    stack: Vec<u32>,
+    on_stack: HashSet<u32>,
+    components: Vec<Vec<u32>>,
+}
+
+impl Tarjan<'_> {
+    fn visit(&mut self, v: u32) {
+        let i = self.index.len();
+        self.index.insert(v, i);
+        self.low.insert(v, i);
+        self.stack.push(v);
+        self.on_stack.insert(v);
+
+        for &w in &self.graph[&v] {
+            if !self.index.contains_key(&w) {
+                self.visit(w);
+                let low = self.low[&v].min(self.low[&w]);
+                self.low.insert(v, low);
+            } else if self.on_stack.contains(&w) {
+                let low = self.low[&v].min(self.index[&w]);
+                self.low.insert(v, low);
+            }
+        }
+
+        if self.low[&v] == self.index[&v] {
+            let mut component = Vec::new();
+            while let Some(w) = self.stack.pop() {
+                self.on_stack.remove(&w);
+                component.push(w);
+                if w == v {
+                    break;
+                }
+            }
+            self.components.push(component);
+        }
+    }
+}
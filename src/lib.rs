@@ -0,0 +1,32 @@
+use std::{fmt::Display, str::FromStr};
+
+use anyhow::{anyhow, Context};
+
+pub mod day14;
+pub mod day15;
+pub mod day16;
+pub mod day17;
+pub mod day18;
+pub mod day19;
+
+/// Common interface every day exposes so that the `aoc` runner can drive it.
+///
+/// `parse` is called once per input and both parts then work on the parsed value.
+pub trait Solution {
+    type Input;
+    type Output: Display;
+
+    fn parse(input: &str) -> anyhow::Result<Self::Input>;
+    fn part1(input: &Self::Input) -> anyhow::Result<Self::Output>;
+    fn part2(input: &Self::Input) -> anyhow::Result<Self::Output>;
+}
+
+/// Parses the value following a command line `flag`, failing if it's missing or invalid.
+pub fn parse_arg<T: FromStr>(arg: Option<String>, flag: &str) -> anyhow::Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let arg = arg.ok_or_else(|| anyhow!("`{flag}` expects a value"))?;
+    arg.parse()
+        .with_context(|| format!("invalid value for `{flag}`: `{arg}`"))
+}
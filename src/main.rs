@@ -1,3 +1,182 @@
-fn main() {
-    println!("Hello, world!");
+use std::{
+    env::args,
+    fs,
+    io::{self, Write},
+    time::Instant,
+};
+
+use anyhow::{anyhow, bail, Context};
+use aoc_2019::{day14, day15, day16, day17, day18, day19, Solution};
+
+const DAYS: &[u32] = &[14, 15, 16, 17, 18, 19];
+
+const USAGE: &str = "\
+usage: aoc run <day|all> [--part 1|2] [--input <path>]
+       aoc list";
+
+fn main() -> anyhow::Result<()> {
+    let args: Vec<String> = args().skip(1).collect();
+    let mut out = io::stdout().lock();
+    match args.first().map(String::as_str) {
+        Some("run") => run(&args[1..], &mut out),
+        Some("list") => {
+            for day in DAYS {
+                writeln!(out, "day{day}")?;
+            }
+            Ok(())
+        }
+        _ => bail!("{USAGE}"),
+    }
+}
+
+fn run(args: &[String], out: &mut impl Write) -> anyhow::Result<()> {
+    let mut args = args.iter();
+    let day = args.next().ok_or_else(|| anyhow!("{USAGE}"))?;
+
+    let mut parts = vec![1, 2];
+    let mut input = None;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--part" => {
+                let part = args.next().context("`--part` expects a value")?;
+                parts = match part.as_str() {
+                    "1" => vec![1],
+                    "2" => vec![2],
+                    x => bail!("invalid part: `{x}`, expected 1 or 2"),
+                };
+            }
+            "--input" => {
+                input = Some(args.next().context("`--input` expects a path")?);
+            }
+            x => bail!("unexpected argument: `{x}`\n{USAGE}"),
+        }
+    }
+
+    if day == "all" {
+        if input.is_some() {
+            bail!("`--input` can't be combined with running all days");
+        }
+        for &day in DAYS {
+            run_day(day, None, &parts, out)?;
+        }
+        return Ok(());
+    }
+
+    let day = day
+        .trim_start_matches("day")
+        .parse()
+        .with_context(|| format!("invalid day: `{day}`"))?;
+    run_day(day, input.map(String::as_str), &parts, out)
+}
+
+fn run_day(
+    day: u32,
+    input: Option<&str>,
+    parts: &[u8],
+    out: &mut impl Write,
+) -> anyhow::Result<()> {
+    if !DAYS.contains(&day) {
+        bail!("day {day} is not available, see `aoc list`");
+    }
+    let path = match input {
+        Some(path) => path.to_owned(),
+        None => format!("src/day{day}/input.txt"),
+    };
+    let input = fs::read_to_string(&path).with_context(|| format!("can't read `{path}`"))?;
+
+    writeln!(out, "Day {day}:")?;
+    match day {
+        14 => solve::<day14::Day14>(&input, parts, out),
+        15 => solve::<day15::Day15>(&input, parts, out),
+        16 => solve::<day16::Day16>(&input, parts, out),
+        17 => solve::<day17::Day17>(&input, parts, out),
+        18 => solve::<day18::Day18>(&input, parts, out),
+        19 => solve::<day19::Day19>(&input, parts, out),
+        _ => unreachable!("day {day} is listed in `DAYS` but not dispatched"),
+    }
+}
+
+fn solve<S: Solution>(input: &str, parts: &[u8], out: &mut impl Write) -> anyhow::Result<()> {
+    let start = Instant::now();
+    let parsed = S::parse(input)?;
+    writeln!(out, "  parse  ({:.2?})", start.elapsed())?;
+
+    for &part in parts {
+        let start = Instant::now();
+        let ans = match part {
+            1 => S::part1(&parsed)?,
+            _ => S::part2(&parsed)?,
+        };
+        writeln!(out, "  part{part}: {ans} ({:.2?})", start.elapsed())?;
+    }
+    Ok(())
+}
+
+/// Runs the `aoc run` arguments, returning the output without the timings.
+#[cfg(test)]
+fn run_args(args: &[&str]) -> anyhow::Result<Vec<String>> {
+    let args: Vec<String> = args.iter().map(|&arg| arg.to_owned()).collect();
+    let mut out = Vec::new();
+    run(&args, &mut out)?;
+    let lines = String::from_utf8(out)?
+        .lines()
+        .map(|line| match line.rsplit_once(" (") {
+            Some((line, _)) => line.trim_end().to_owned(),
+            None => line.to_owned(),
+        })
+        .collect();
+    Ok(lines)
+}
+
+#[test]
+fn run_parts() {
+    assert_eq!(
+        run_args(&["17", "--part", "1"]).unwrap(),
+        ["Day 17:", "  parse", "  part1: 247"]
+    );
+    assert_eq!(
+        run_args(&["day14", "--part", "2"]).unwrap(),
+        ["Day 14:", "  parse", "  part2: 3687727854171"]
+    );
+    let err = run_args(&["17", "--part", "3"]).unwrap_err();
+    assert_eq!(err.to_string(), "invalid part: `3`, expected 1 or 2");
+    let err = run_args(&["17", "--part"]).unwrap_err();
+    assert_eq!(err.to_string(), "`--part` expects a value");
+}
+
+#[test]
+fn run_input() {
+    assert_eq!(
+        run_args(&["14", "--input", "src/day14/test_input.txt", "--part", "1"]).unwrap(),
+        ["Day 14:", "  parse", "  part1: 165"]
+    );
+    let err = run_args(&["14", "--input", "no/such/input.txt"]).unwrap_err();
+    assert_eq!(err.to_string(), "can't read `no/such/input.txt`");
+    let err = run_args(&["all", "--input", "src/day14/input.txt"]).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "`--input` can't be combined with running all days"
+    );
+}
+
+#[test]
+fn run_all() {
+    let out = run_args(&["all", "--part", "1"]).unwrap();
+    let days: Vec<_> = out.iter().filter(|line| line.starts_with("Day")).collect();
+    assert_eq!(
+        days,
+        ["Day 14:", "Day 15:", "Day 16:", "Day 17:", "Day 18:", "Day 19:"]
+    );
+    assert_eq!(out.len(), 3 * DAYS.len());
+    assert!(!out.iter().any(|line| line.starts_with("  part2")));
+}
+
+#[test]
+fn unknown_day() {
+    let err = run_args(&["20"]).unwrap_err();
+    assert_eq!(err.to_string(), "day 20 is not available, see `aoc list`");
+    let err = run_args(&["20", "--input", "no/such/input.txt"]).unwrap_err();
+    assert_eq!(err.to_string(), "day 20 is not available, see `aoc list`");
+    let err = run_args(&["x"]).unwrap_err();
+    assert_eq!(err.to_string(), "invalid day: `x`");
 }